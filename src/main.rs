@@ -1,40 +1,605 @@
-use chrono::{Local, NaiveDate, NaiveTime};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
+    style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
-    text::{Line, Span},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, BorderType},
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table},
     Terminal,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 use std::io;
+use std::time::{Duration, Instant};
+
+/// Put between notes from different files by `keep merge`.
+const MERGE_SEPARATOR: &str = "-------- merged --------";
+
+/// How long the first key of a sequence like `dd` or `gg` waits for the second.
+const PENDING_KEY_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// How often the main loop wakes up without input, e.g. to autosave notes.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// Notable changes listed in the About popup (`V`), newest first.
+const RECENT_CHANGES: &[&str] = &[
+    "day_boundary_hour keeps late nights on the previous day",
+    "Tasks can span several lines (Shift+Enter)",
+    "completed_placement keeps finished tasks in place or hides them",
+    "advance_on_complete moves on after finishing a task",
+    "keep stats prints completion counts, with --json",
+    "The header counts down to the next timed task",
+];
+
+/// Unfinished tasks at least this many days old get an age badge.
+const STALE_TASK_DAYS: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    fn next(self) -> Self {
+        match self {
+            Status::Todo => Status::InProgress,
+            Status::InProgress => Status::Done,
+            Status::Done => Status::Todo,
+        }
+    }
+
+    /// Order used to break ties in the task list: work in progress first,
+    /// finished work last.
+    fn sort_rank(self) -> u8 {
+        match self {
+            Status::InProgress => 0,
+            Status::Todo => 1,
+            Status::Done => 2,
+        }
+    }
+}
+
+// Older data files store a `completed: bool` instead of a status, so accept
+// either form when loading.
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Legacy(bool),
+            Named(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Legacy(true) => Ok(Status::Done),
+            Repr::Legacy(false) => Ok(Status::Todo),
+            Repr::Named(name) => match name.as_str() {
+                "todo" => Ok(Status::Todo),
+                "in_progress" => Ok(Status::InProgress),
+                "done" => Ok(Status::Done),
+                other => Err(serde::de::Error::unknown_variant(other, &["todo", "in_progress", "done"])),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TaskKind {
+    #[default]
+    Task,
+    Heading, // A section divider within a day, never completed
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Task {
+    #[serde(default)]
+    id: u64, // Stable across saves, unlike the task's index; 0 until one is assigned
     content: String,
-    completed: bool,
+    #[serde(default, alias = "completed")]
+    status: Status,
     date: Option<NaiveDate>,
     start_time: Option<NaiveTime>,
     end_time: Option<NaiveTime>,
+    #[serde(default)]
+    pinned: bool, // Shown on every day until completed
+    #[serde(default)]
+    kind: TaskKind,
+    #[serde(default)]
+    label: Option<String>, // Name of a color label from the settings
+    #[serde(default)]
+    reminder_minutes: Option<u32>, // Remind this long before the start; overrides the global setting
+    #[serde(default)]
+    completed_at: Option<NaiveDateTime>, // When the task was last marked done
+    #[serde(default)]
+    created_at: Option<NaiveDateTime>, // Missing for tasks saved before this was tracked
+    #[serde(default)]
+    logged_minutes: u32, // Time spent, logged by hand with `+`
+    #[serde(default)]
+    location: Option<String>, // Where it happens: an address, a place name or a map link
+    #[serde(default)]
+    focus: bool, // The one most important task of its day, listed first
+    #[serde(default)]
+    blocked_by: Option<u64>, // Id of a task that has to be finished first
+}
+
+impl Task {
+    /// Change the status, stamping when the task was completed.
+    fn set_status(&mut self, status: Status) {
+        if status == Status::Done && !self.is_done() {
+            self.completed_at = Some(Local::now().naive_local());
+        } else if status != Status::Done {
+            self.completed_at = None;
+        }
+        self.status = status;
+    }
+
+    fn is_done(&self) -> bool {
+        self.status == Status::Done
+    }
+
+    /// Move the task to another day. A day keeps a single focus, so the
+    /// task stops being one when it leaves its day.
+    fn move_to(&mut self, date: Option<NaiveDate>) {
+        if self.date != date {
+            self.focus = false;
+        }
+        self.date = date;
+    }
+
+    fn is_heading(&self) -> bool {
+        self.kind == TaskKind::Heading
+    }
+
+    /// The first line of the content, for places with room for one.
+    fn title(&self) -> &str {
+        self.content.lines().next().unwrap_or_default()
+    }
+
+    /// Rows the task takes in the table: one per line of its content.
+    fn row_height(&self) -> usize {
+        self.content.lines().count().max(1)
+    }
+
+    /// Whole days since the task was created, if it's unfinished and old enough to flag.
+    fn stale_days(&self, today: NaiveDate) -> Option<i64> {
+        if self.is_done() {
+            return None;
+        }
+        let age = (today - self.created_at?.date()).num_days();
+        (age >= STALE_TASK_DAYS).then_some(age)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ViewMode {
     Scheduled,
+    All,
+    Week,
     Notes,
 }
 
+/// Which tasks the list shows by whether they have a start time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimeFilter {
+    All,
+    Timed,
+    Untimed,
+}
+
+impl TimeFilter {
+    fn next(self) -> Self {
+        match self {
+            TimeFilter::All => TimeFilter::Timed,
+            TimeFilter::Timed => TimeFilter::Untimed,
+            TimeFilter::Untimed => TimeFilter::All,
+        }
+    }
+
+    /// Headings stay put so the day keeps its shape.
+    fn matches(self, task: &Task) -> bool {
+        match self {
+            _ if task.is_heading() => true,
+            TimeFilter::All => true,
+            TimeFilter::Timed => task.start_time.is_some(),
+            TimeFilter::Untimed => task.start_time.is_none(),
+        }
+    }
+}
+
+/// Part of the day a task starts in, for grouping the week view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TimeBucket {
+    Morning,
+    Afternoon,
+    Evening,
+    NoTime,
+}
+
+impl TimeBucket {
+    fn for_time(start: Option<NaiveTime>) -> Self {
+        match start {
+            Some(t) if t < NaiveTime::from_hms_opt(12, 0, 0).unwrap() => TimeBucket::Morning,
+            Some(t) if t < NaiveTime::from_hms_opt(17, 0, 0).unwrap() => TimeBucket::Afternoon,
+            Some(_) => TimeBucket::Evening,
+            None => TimeBucket::NoTime,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TimeBucket::Morning => "Morning",
+            TimeBucket::Afternoon => "Afternoon",
+            TimeBucket::Evening => "Evening",
+            TimeBucket::NoTime => "No time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+enum TimeFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OverdueSort {
+    #[default]
+    OldestFirst,
+    NewestFirst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+/// Where finished tasks go in the task list.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CompletedPlacement {
+    InPlace, // Stay where they are, struck through
+    #[default]
+    Bottom,  // Gather in a foldable "Completed" section
+    Hidden,  // Aren't listed at all
+}
+
+/// How the data file is written. Loading goes by the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StorageFormat {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl StorageFormat {
+    const ALL: [StorageFormat; 3] = [StorageFormat::Json, StorageFormat::Toml, StorageFormat::Yaml];
+
+    fn extension(self) -> &'static str {
+        match self {
+            StorageFormat::Json => "json",
+            StorageFormat::Toml => "toml",
+            StorageFormat::Yaml => "yaml",
+        }
+    }
+
+    /// The format a file name's extension stands for, defaulting to JSON.
+    fn from_path(path: &str) -> Self {
+        match std::path::Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => StorageFormat::Toml,
+            Some("yaml" | "yml") => StorageFormat::Yaml,
+            _ => StorageFormat::Json,
+        }
+    }
+}
+
+/// User preferences, kept in their own file apart from the tasks so merging
+/// or migrating data can't lose them. Every field falls back to its default
+/// so older files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    time_format: TimeFormat,
+    overdue_sort: OverdueSort,
+    autocomplete: bool, // Suggest completions from past task content
+    search_notes: bool, // Whether `/` search includes notes by default
+    show_sidebar: bool,
+    daily_capacity: usize, // Most unfinished tasks per day when planning the inbox
+    density: Density,
+    overdue_window_days: Option<u32>, // Only look this far back for overdue tasks; None = no limit
+    labels: BTreeMap<String, String>, // Label name -> color, as "#rrggbb" or a color name
+    week_buckets: bool, // Group each day of the week view into morning/afternoon/evening
+    notes_autosave_ms: u64, // Save notes once they've sat unsaved this long; 0 = off
+    work_start: NaiveTime, // Working hours: the free time finder and week view stay within these
+    work_end: NaiveTime,
+    hide_done_after_secs: Option<u64>, // Collapse finished tasks this long after completing them; None = off
+    reminder_minutes: Option<u32>, // Remind this long before timed tasks start; None = off
+    reminder_command: Option<String>, // Shell command run for each reminder instead of the bell
+    date_format: String, // strftime format for full dates, e.g. in the header
+    sidebar_width: u16, // Overdue sidebar width on wide terminals, adjusted with [ and ]
+    confirm_delete_above: usize, // Deleting more tasks than this at once asks first
+    notes_line_numbers: bool, // Show a line number gutter in the notes editor
+    ascii_mode: bool, // Plain ASCII instead of emoji and box-drawing, for limited terminals
+    mouse: bool, // Click to select and toggle tasks, scroll to move the selection
+    one_line_rows: bool, // "09:00 ○ Task" rows instead of separate time columns
+    notes_max_chars: Option<usize>, // Stop typing into the notes at this length; None = unlimited
+    storage_format: StorageFormat, // File format the tasks are saved in
+    trash_days: Option<u32>, // Deleted tasks wait in the trash this long; None deletes them outright
+    compact_header: bool, // One borderless header line instead of a boxed one
+    auto_sort: bool, // Sort each day by time; off keeps the order tasks were arranged in
+    advance_on_complete: bool, // Move the selection to the next unfinished task after completing one
+    wrap_navigation: bool, // Up on the first task goes to the last, and Down on the last to the first
+    completed_placement: CompletedPlacement,
+    accents: Accents, // Each view's color for its header, borders and key hints
+    day_boundary_hour: u32, // Hours past midnight that still count as the previous day
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            time_format: TimeFormat::default(),
+            overdue_sort: OverdueSort::default(),
+            autocomplete: true,
+            search_notes: true,
+            show_sidebar: true,
+            daily_capacity: 5,
+            density: Density::default(),
+            overdue_window_days: None,
+            labels: BTreeMap::from([
+                ("personal".to_string(), "#7bc96f".to_string()),
+                ("urgent".to_string(), "red".to_string()),
+                ("work".to_string(), "#4f9dde".to_string()),
+            ]),
+            week_buckets: true,
+            notes_autosave_ms: 2000,
+            work_start: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            work_end: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            hide_done_after_secs: None,
+            reminder_minutes: None,
+            reminder_command: None,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            sidebar_width: 35,
+            confirm_delete_above: 1,
+            notes_line_numbers: false,
+            ascii_mode: false,
+            mouse: false,
+            one_line_rows: false,
+            notes_max_chars: None,
+            storage_format: StorageFormat::default(),
+            trash_days: None,
+            compact_header: false,
+            auto_sort: true,
+            advance_on_complete: false,
+            wrap_navigation: true,
+            completed_placement: CompletedPlacement::default(),
+            accents: Accents::default(),
+            day_boundary_hour: 0,
+        }
+    }
+}
+
+/// The calendar day `now` belongs to when days end `boundary_hour` hours
+/// after midnight.
+fn effective_date(now: NaiveDateTime, boundary_hour: u32) -> NaiveDate {
+    (now - chrono::Duration::hours(boundary_hour.min(23).into())).date()
+}
+
+/// The accent color of each view, as "#rrggbb" or a color name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Accents {
+    scheduled: String,
+    all: String,
+    week: String,
+    notes: String,
+}
+
+impl Default for Accents {
+    fn default() -> Self {
+        Self {
+            scheduled: "cyan".to_string(),
+            all: "yellow".to_string(),
+            week: "green".to_string(),
+            notes: "#9664c8".to_string(),
+        }
+    }
+}
+
+/// Full date format used when `date_format` is missing or invalid.
+const DEFAULT_DATE_FORMAT: &str = "%A, %B %d, %Y";
+
+/// Borders drawn with `+`, `-` and `|` when `ascii_mode` is on.
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+impl Config {
+    fn path() -> String {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        format!("{}/.keep_config.json", home)
+    }
+
+    /// Load the preferences, falling back to the `settings` object that
+    /// older versions kept inside the data file.
+    fn load() -> Self {
+        match std::fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => std::fs::read_to_string(AppData::path_for(StorageFormat::Json))
+                .ok()
+                .and_then(|content| Self::from_legacy(&content))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The preferences embedded in an old data file, if it has any.
+    fn from_legacy(data: &str) -> Option<Self> {
+        let mut data: serde_json::Value = serde_json::from_str(data).ok()?;
+        serde_json::from_value(data.get_mut("settings")?.take()).ok()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(), content)?;
+        Ok(())
+    }
+
+    /// Give preferences still kept in the data file their own file, before
+    /// the data file is rewritten without them.
+    fn migrate() -> io::Result<()> {
+        if !std::path::Path::new(&Self::path()).exists() {
+            Self::load().save()?;
+        }
+        Ok(())
+    }
+
+    /// The date that counts as today, which stays on the previous day until
+    /// `day_boundary_hour` for people who plan past midnight.
+    fn today(&self) -> NaiveDate {
+        effective_date(Local::now().naive_local(), self.day_boundary_hour)
+    }
+
+    /// Pick the decorated or the plain spelling of a symbol.
+    fn glyph<'a>(&self, fancy: &'a str, ascii: &'a str) -> &'a str {
+        if self.ascii_mode { ascii } else { fancy }
+    }
+
+    fn border_set(&self) -> border::Set {
+        if self.ascii_mode { ASCII_BORDER } else { border::ROUNDED }
+    }
+
+    /// A date written out with `date_format`, falling back to the default
+    /// format when the configured one doesn't parse or asks for more than a
+    /// date has, like `%H` or `%Z`.
+    fn full_date(&self, date: NaiveDate) -> String {
+        use std::fmt::Write;
+        let valid = !StrftimeItems::new(&self.date_format).any(|item| matches!(item, Item::Error));
+        let mut text = String::new();
+        if valid && write!(text, "{}", date.format(&self.date_format)).is_ok() {
+            return text;
+        }
+        date.format(DEFAULT_DATE_FORMAT).to_string()
+    }
+
+    fn within_work_hours(&self, time: NaiveTime) -> bool {
+        time >= self.work_start && time < self.work_end
+    }
+
+    /// The accent color of a view, falling back to its default when the
+    /// configured one can't be read.
+    fn accent(&self, view: ViewMode) -> Color {
+        let (color, fallback) = match view {
+            ViewMode::Scheduled => (&self.accents.scheduled, Color::Cyan),
+            ViewMode::All => (&self.accents.all, Color::Yellow),
+            ViewMode::Week => (&self.accents.week, Color::Green),
+            ViewMode::Notes => (&self.accents.notes, Color::Rgb(150, 100, 200)),
+        };
+        color.trim().parse().unwrap_or(fallback)
+    }
+
+    /// Color for a label, or gray when it's unknown or its color doesn't parse.
+    fn label_color(&self, label: &str) -> Color {
+        self.labels
+            .get(label)
+            .and_then(|color| color.trim().parse::<Color>().ok())
+            .unwrap_or(Color::DarkGray)
+    }
+}
+
+/// A reusable task shape that pre-fills the add form, independent of any date.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Template {
+    name: String,
+    content: String,
+    start_time: Option<NaiveTime>,
+    end_time: Option<NaiveTime>,
+    #[serde(default)]
+    routine: bool, // Added to a day by the "plan my routine" key
+}
+
+/// A deleted task waiting in the trash to be restored or expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashedTask {
+    task: Task,
+    deleted_at: NaiveDateTime,
+}
+
+/// What `keep stats --json` prints. Fields are only ever added, so
+/// scripts reading it keep working.
+#[derive(Debug, Serialize)]
+struct StatsReport {
+    from: NaiveDate,
+    to: NaiveDate,
+    days: Vec<DayStats>,
+    total: usize,
+    done: usize,
+    completed: usize,
+    completion_rate: Option<f64>, // done / total; None when nothing was scheduled
+}
+
+#[derive(Debug, Serialize)]
+struct DayStats {
+    date: NaiveDate,
+    total: usize,     // Tasks scheduled for the day
+    done: usize,      // How many of those are done
+    completed: usize, // Tasks marked done on the day, whatever their date
+}
+
+/// Problems `keep doctor` found, as indices into the task list.
+#[derive(Debug, Default, PartialEq)]
+struct Diagnosis {
+    empty: Vec<usize>,      // Blank content
+    reversed: Vec<usize>,   // End time before the start time
+    duplicates: Vec<usize>, // Same content, date and times as an earlier task
+}
+
+impl Diagnosis {
+    fn is_healthy(&self) -> bool {
+        self.empty.is_empty() && self.reversed.is_empty() && self.duplicates.is_empty()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct AppData {
     tasks: Vec<Task>,
     #[serde(default)]
     notes: String,
+    #[serde(default)]
+    templates: Vec<Template>,
+    #[serde(default)]
+    trash: Vec<TrashedTask>, // Newest last
+    #[serde(default)]
+    last_id: u64, // Highest task id handed out so far, so ids of deleted tasks aren't reused
 }
 
 impl AppData {
@@ -42,235 +607,2816 @@ impl AppData {
         Self {
             tasks: Vec::new(),
             notes: String::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            last_id: 0,
         }
     }
 
-    fn load() -> io::Result<Self> {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let path = format!("{}/.keep_tasks.json", home);
+    /// Example tasks and a welcome note for someone opening Keep for the
+    /// first time.
+    fn sample(today: NaiveDate) -> Self {
+        let example = |content: &str, date: NaiveDate, start: Option<u32>| Task {
+            id: 0,
+            content: content.to_string(),
+            status: Status::Todo,
+            date: Some(date),
+            start_time: start.and_then(|hour| NaiveTime::from_hms_opt(hour, 0, 0)),
+            end_time: start.and_then(|hour| NaiveTime::from_hms_opt(hour, 30, 0)),
+            pinned: false,
+            kind: TaskKind::Task,
+            label: None,
+            reminder_minutes: None,
+            completed_at: None,
+            created_at: None,
+            logged_minutes: 0,
+            location: None,
+            focus: false,
+            blocked_by: None,
+        };
+        let tomorrow = today.succ_opt().unwrap_or(today);
+
+        let mut data = Self::new();
+        data.tasks = vec![
+            example("Welcome to Keep! Press Space to mark me done", today, None),
+            example("Press e to edit me, or n to add a task of your own", today, Some(9)),
+            example("Clear these examples with dd, or C once they're done", today, None),
+            example("Press l to visit tomorrow, and h to come back", tomorrow, None),
+        ];
+        data.assign_ids();
+        data.notes = [
+            "# Welcome to Keep",
+            "",
+            "This is your notepad; Tab cycles between the task views and here.",
+            "",
+            "- n / e / dd: add, edit and delete tasks",
+            "- Space: mark a task done",
+            "- h / l: previous / next day",
+            "- / : search everything",
+            "- q: quit (everything is saved as you go)",
+            "",
+            "Delete this note whenever you like.",
+            "",
+        ]
+        .join("\n");
+        data
+    }
+
+    /// Whether there's no data file yet, in any format.
+    fn is_first_run(format: StorageFormat) -> bool {
+        !std::path::Path::new(&Self::path(format)).exists()
+    }
+
+    fn home() -> String {
+        std::env::var("HOME").unwrap_or_else(|_| ".".to_string())
+    }
+
+    fn path_for(format: StorageFormat) -> String {
+        Self::path_in(&Self::home(), format)
+    }
 
+    fn path_in(dir: &str, format: StorageFormat) -> String {
+        format!("{}/.keep_tasks.{}", dir, format.extension())
+    }
+
+    /// The data file to read: the one in the configured format, or else one
+    /// in another format, e.g. right after `storage_format` was changed.
+    fn path(format: StorageFormat) -> String {
+        Self::find_in(&Self::home(), format)
+    }
+
+    fn find_in(dir: &str, format: StorageFormat) -> String {
+        let preferred = Self::path_in(dir, format);
+        if std::path::Path::new(&preferred).exists() {
+            return preferred;
+        }
+        StorageFormat::ALL
+            .iter()
+            .map(|&other| Self::path_in(dir, other))
+            .find(|path| std::path::Path::new(path).exists())
+            .unwrap_or(preferred)
+    }
+
+    fn load(format: StorageFormat) -> io::Result<Self> {
+        let path = Self::path(format);
         match std::fs::read_to_string(&path) {
             Ok(content) => {
-                Ok(serde_json::from_str(&content).unwrap_or_else(|_| Self::new()))
+                Ok(Self::decode(&content, StorageFormat::from_path(&path)).unwrap_or_else(|_| Self::new()))
             }
             Err(_) => Ok(Self::new()),
         }
     }
 
-    fn save(&self) -> io::Result<()> {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let path = format!("{}/.keep_tasks.json", home);
-        let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&path, content)?;
+    /// Load the data file for a command that rewrites it: an empty list when
+    /// there's no file yet, but exit rather than start empty when the file
+    /// can't be read, since saving would replace it.
+    fn load_for_update(format: StorageFormat) -> Self {
+        let path = Self::path(format);
+        if !std::path::Path::new(&path).exists() {
+            return Self::new();
+        }
+        match Self::load_from(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("{} can't be read, so it was left alone: {}", path, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Load another data file, failing loudly instead of starting empty.
+    fn load_from(path: &str) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::decode(&content, StorageFormat::from_path(path))
+    }
+
+    fn save(&self, format: StorageFormat) -> io::Result<()> {
+        self.save_in(&Self::home(), format)
+    }
+
+    /// Save in `format`, then rename data files left in other formats to
+    /// `.bak` so a stale one is never read again after switching back.
+    fn save_in(&self, dir: &str, format: StorageFormat) -> io::Result<()> {
+        self.save_to(&Self::path_in(dir, format))?;
+        for other in StorageFormat::ALL.iter().filter(|&&other| other != format) {
+            let old = Self::path_in(dir, *other);
+            if std::path::Path::new(&old).exists() {
+                std::fs::rename(&old, format!("{}.bak", old))?;
+            }
+        }
         Ok(())
     }
 
-    fn tasks_for_date(&self, date: &NaiveDate) -> Vec<(usize, &Task)> {
-        self.tasks
-            .iter()
-            .enumerate()
-            .filter(|(_, t)| t.date.as_ref() == Some(date))
-            .collect()
+    fn save_to(&self, path: &str) -> io::Result<()> {
+        let content = self.encode(StorageFormat::from_path(path))?;
+        std::fs::write(path, content)?;
+        Ok(())
     }
 
-    fn overdue_tasks(&self, current_date: &NaiveDate) -> Vec<(usize, &Task)> {
-        self.tasks
-            .iter()
-            .enumerate()
-            .filter(|(_, t)| {
-                if let Some(task_date) = t.date {
-                    task_date < *current_date && !t.completed
-                } else {
-                    false
+    fn encode(&self, format: StorageFormat) -> io::Result<String> {
+        let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+        match format {
+            StorageFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            StorageFormat::Toml => toml::to_string_pretty(self).map_err(|err| invalid(err.to_string())),
+            StorageFormat::Yaml => serde_yaml::to_string(self).map_err(|err| invalid(err.to_string())),
+        }
+    }
+
+    /// The tasks as CSV with the columns date,start,end,completed,content,
+    /// or tab-separated with `delimiter` b'\t'. Headings are left out.
+    fn to_csv(&self, delimiter: u8) -> io::Result<String> {
+        let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(Vec::new());
+        writer.write_record(CSV_COLUMNS)?;
+        let date = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+        let time = |t: Option<NaiveTime>| t.map(|t| t.format("%H:%M").to_string()).unwrap_or_default();
+        for task in self.tasks.iter().filter(|t| !t.is_heading()) {
+            writer.write_record([
+                date(task.date),
+                time(task.start_time),
+                time(task.end_time),
+                task.is_done().to_string(),
+                task.content.clone(),
+            ])?;
+        }
+        let bytes = writer.into_inner().map_err(|err| io::Error::other(err.to_string()))?;
+        String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Tasks read from CSV laid out like `to_csv` writes it. Columns are
+    /// found by their header, so a spreadsheet may reorder them or add more.
+    /// Blank rows are skipped, but a row with data and no content is an error.
+    fn tasks_from_csv(content: &str, delimiter: u8) -> Result<Vec<Task>, String> {
+        let now = Local::now().naive_local();
+        let mut reader = csv::ReaderBuilder::new().delimiter(delimiter).from_reader(content.as_bytes());
+        let headers = reader.headers().map_err(|err| err.to_string())?.clone();
+        let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let Some(content_column) = column("content") else {
+            return Err("Missing a content column".to_string());
+        };
+        let [date_column, start_column, end_column, completed_column] =
+            ["date", "start", "end", "completed"].map(column);
+
+        let mut tasks = Vec::new();
+        for (row, record) in reader.records().enumerate() {
+            let record = record.map_err(|err| err.to_string())?;
+            let field = |column: Option<usize>| column.and_then(|c| record.get(c)).unwrap_or("").trim();
+            // Line 1 is the header
+            let line = row + 2;
+            if field(Some(content_column)).is_empty() {
+                if record.iter().all(|value| value.trim().is_empty()) {
+                    continue;
                 }
-            })
-            .collect()
+                return Err(format!("Line {}: content is empty", line));
+            }
+            let date = match field(date_column) {
+                "" => None,
+                date => Some(
+                    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| format!("Line {}: invalid date '{}' - use YYYY-MM-DD", line, date))?,
+                ),
+            };
+            let time = |column| {
+                parse_time_input(field(column), TimeFormat::TwentyFourHour).map_err(|err| format!("Line {}: {}", line, err))
+            };
+            let done = match field(completed_column).to_ascii_lowercase().as_str() {
+                "" | "false" | "no" | "0" => false,
+                "true" | "yes" | "1" | "x" => true,
+                other => return Err(format!("Line {}: completed should be true or false, not '{}'", line, other)),
+            };
+            tasks.push(Task {
+                id: 0,
+                content: field(Some(content_column)).to_string(),
+                status: if done { Status::Done } else { Status::Todo },
+                date,
+                start_time: time(start_column)?,
+                end_time: time(end_column)?,
+                pinned: false,
+                kind: TaskKind::Task,
+                label: None,
+                reminder_minutes: None,
+                completed_at: None,
+                created_at: Some(now),
+                logged_minutes: 0,
+                location: None,
+                focus: false,
+                blocked_by: None,
+            });
+        }
+        Ok(tasks)
     }
-}
 
-struct App {
-    data: AppData,
-    current_date: NaiveDate,
-    selected_task: usize,
-    input_mode: bool,
-    input_buffer: String,
-    start_time_buffer: String,
-    end_time_buffer: String,
-    time_input_field: usize, // 0 = task, 1 = start_time, 2 = end_time
-    editing_task_idx: Option<usize>, // None = adding new task, Some(idx) = editing task
-    notes_buffer: String,
-    notes_cursor: usize, // Cursor position in notes buffer
-    should_quit: bool,
-    view_mode: ViewMode,
-}
+    fn decode(content: &str, format: StorageFormat) -> io::Result<Self> {
+        let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+        let mut data: Self = match format {
+            StorageFormat::Json => serde_json::from_str(content).map_err(|err| invalid(err.to_string())),
+            StorageFormat::Toml => toml::from_str(content).map_err(|err| invalid(err.to_string())),
+            StorageFormat::Yaml => serde_yaml::from_str(content).map_err(|err| invalid(err.to_string())),
+        }?;
+        data.assign_ids();
+        Ok(data)
+    }
 
-impl App {
-    fn new() -> io::Result<Self> {
-        let data = AppData::load()?;
-        let notes_buffer = data.notes.clone();
-        let notes_cursor = notes_buffer.len();
-        Ok(Self {
-            data,
-            current_date: Local::now().date_naive(),
-            selected_task: 0,
-            input_mode: false,
-            input_buffer: String::new(),
-            start_time_buffer: String::new(),
-            end_time_buffer: String::new(),
-            time_input_field: 0,
-            editing_task_idx: None,
-            notes_buffer,
+    /// Give a fresh id to every task without one (from older files) and to
+    /// all but the first of any tasks sharing one (from hand edits), so ids
+    /// are unique across the list and the trash.
+    fn assign_ids(&mut self) {
+        let mut last = self.highest_id();
+        let mut seen = HashSet::new();
+        let trashed = self.trash.iter_mut().map(|t| &mut t.task);
+        for task in self.tasks.iter_mut().chain(trashed) {
+            if task.id == 0 || !seen.insert(task.id) {
+                last += 1;
+                task.id = last;
+            }
+        }
+        self.last_id = last;
+    }
+
+    /// The highest id handed out so far, counting tasks in the trash that
+    /// may come back and tasks deleted for good.
+    fn highest_id(&self) -> u64 {
+        let trashed = self.trash.iter().map(|t| &t.task);
+        let highest = self.tasks.iter().chain(trashed).map(|t| t.id).max().unwrap_or(0);
+        highest.max(self.last_id)
+    }
+
+    /// Hand out an id no task has or had, so a reference to a deleted task
+    /// never ends up pointing at a new one.
+    fn next_id(&mut self) -> u64 {
+        self.last_id = self.highest_id() + 1;
+        self.last_id
+    }
+
+    /// The unfinished task `task` is waiting on, if any.
+    fn blocker(&self, task: &Task) -> Option<&Task> {
+        let id = task.blocked_by?;
+        self.tasks.iter().find(|t| t.id == id && !t.is_done())
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        let mut diagnosis = Diagnosis::default();
+        let mut seen = HashSet::new();
+        for (idx, task) in self.tasks.iter().enumerate() {
+            if task.content.trim().is_empty() {
+                diagnosis.empty.push(idx);
+                continue;
+            }
+            if matches!((task.start_time, task.end_time), (Some(start), Some(end)) if end < start) {
+                diagnosis.reversed.push(idx);
+            }
+            if !seen.insert((task.content.trim(), task.date, task.start_time, task.end_time)) {
+                diagnosis.duplicates.push(idx);
+            }
+        }
+        diagnosis
+    }
+
+    /// Drop empty and duplicate tasks and clear end times that come before
+    /// the start. Returns what was wrong.
+    fn repair(&mut self) -> Diagnosis {
+        let diagnosis = self.diagnose();
+        for &idx in &diagnosis.reversed {
+            self.tasks[idx].end_time = None;
+        }
+        let mut idx = 0;
+        self.tasks.retain(|_| {
+            let keep = !diagnosis.empty.contains(&idx) && !diagnosis.duplicates.contains(&idx);
+            idx += 1;
+            keep
+        });
+        diagnosis
+    }
+
+    /// Fold another file into this one. Tasks with the same content, date and
+    /// times are only kept once, notes are joined with a separator and
+    /// templates are added when their name is new. Returns how many tasks
+    /// were added and how many were skipped as duplicates.
+    fn merge(&mut self, other: AppData) -> (usize, usize) {
+        let mut seen: HashMap<_, u64> = self
+            .tasks
+            .iter()
+            .map(|t| ((t.content.clone(), t.date, t.start_time, t.end_time), t.id))
+            .collect();
+        // Added tasks get new ids; a skipped one stands for the task it duplicates
+        let mut new_ids = HashMap::new();
+        let mut last = self.highest_id();
+        let first_added = self.tasks.len();
+        let (mut added, mut skipped) = (0, 0);
+        for mut task in other.tasks {
+            match seen.entry((task.content.clone(), task.date, task.start_time, task.end_time)) {
+                Entry::Occupied(existing) => {
+                    new_ids.insert(task.id, *existing.get());
+                    skipped += 1;
+                }
+                Entry::Vacant(slot) => {
+                    last += 1;
+                    new_ids.insert(task.id, last);
+                    slot.insert(last);
+                    task.id = last;
+                    self.tasks.push(task);
+                    added += 1;
+                }
+            }
+        }
+        self.last_id = last;
+        for task in &mut self.tasks[first_added..] {
+            task.blocked_by = task.blocked_by.and_then(|id| new_ids.get(&id).copied());
+        }
+
+        let other_notes = other.notes.trim();
+        if self.notes.trim().is_empty() {
+            self.notes = other.notes;
+        } else if !other_notes.is_empty() && !self.notes.contains(other_notes) {
+            self.notes = format!("{}\n\n{}\n\n{}", self.notes.trim_end(), MERGE_SEPARATOR, other_notes);
+        }
+
+        for template in other.templates {
+            if !self.templates.iter().any(|t| t.name == template.name) {
+                self.templates.push(template);
+            }
+        }
+
+        (added, skipped)
+    }
+
+    /// Drop trashed tasks deleted more than `days` days before `now`,
+    /// returning how many went.
+    fn expire_trash(&mut self, now: NaiveDateTime, days: u32) -> usize {
+        let before = self.trash.len();
+        let cutoff = now - chrono::Duration::days(days.into());
+        self.trash.retain(|trashed| trashed.deleted_at > cutoff);
+        before - self.trash.len()
+    }
+
+    /// Minutes logged against the tasks dated `date`.
+    fn logged_minutes_on(&self, date: NaiveDate) -> u32 {
+        self.tasks.iter().filter(|t| t.date == Some(date)).map(|t| t.logged_minutes).sum()
+    }
+
+    /// Tasks completed on each of the `days` days up to and including `today`,
+    /// oldest first.
+    fn completions_per_day(&self, today: NaiveDate, days: usize) -> Vec<usize> {
+        let mut counts = vec![0; days];
+        for completed in self.tasks.iter().filter_map(|t| t.completed_at) {
+            let ago = (today - completed.date()).num_days();
+            if (0..days as i64).contains(&ago) {
+                counts[days - 1 - ago as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Per-day counts for the `days` days up to and including `today`.
+    fn stats(&self, today: NaiveDate, days: usize) -> StatsReport {
+        let completions = self.completions_per_day(today, days);
+        let from = today - chrono::Duration::days(days as i64 - 1);
+        let days: Vec<DayStats> = from
+            .iter_days()
+            .zip(completions)
+            .map(|(date, completed)| {
+                let scheduled = self.tasks.iter().filter(|t| t.date == Some(date) && !t.is_heading());
+                let (total, done) = scheduled.fold((0, 0), |(total, done), t| (total + 1, done + usize::from(t.is_done())));
+                DayStats { date, total, done, completed }
+            })
+            .collect();
+        let total = days.iter().map(|d| d.total).sum();
+        let done = days.iter().map(|d| d.done).sum();
+        StatsReport {
+            from,
+            to: today,
+            total,
+            done,
+            completed: days.iter().map(|d| d.completed).sum(),
+            completion_rate: (total > 0).then(|| done as f64 / total as f64),
+            days,
+        }
+    }
+
+    /// Every date that has tasks, in order, with how many (and how many are done).
+    fn task_dates(&self) -> Vec<(NaiveDate, usize, usize)> {
+        let mut dates: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+        for task in self.tasks.iter().filter(|t| !t.is_heading()) {
+            if let Some(date) = task.date {
+                let counts = dates.entry(date).or_default();
+                counts.0 += 1;
+                counts.1 += usize::from(task.is_done());
+            }
+        }
+        dates.into_iter().map(|(date, (total, done))| (date, total, done)).collect()
+    }
+
+    /// The earliest unfinished task with a start time at or after `now`.
+    fn next_upcoming(&self, now: NaiveDateTime) -> Option<&Task> {
+        self.tasks
+            .iter()
+            .filter(|t| !t.is_done() && !t.is_heading())
+            .filter_map(|t| Some((t.date?.and_time(t.start_time?), t)))
+            .filter(|(at, _)| *at >= now)
+            .min_by_key(|(at, _)| *at)
+            .map(|(_, t)| t)
+    }
+
+    /// Tasks dated within the Monday-to-Sunday week containing `date`.
+    fn tasks_for_week(&self, date: &NaiveDate) -> Vec<(usize, &Task)> {
+        let week = date.week(Weekday::Mon);
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !t.is_heading() && t.date.is_some_and(|d| d >= week.first_day() && d <= week.last_day()))
+            .collect()
+    }
+
+    fn tasks_for_date(&self, date: &NaiveDate) -> Vec<(usize, &Task)> {
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.date.as_ref() == Some(date) || (t.pinned && !t.is_done() && !t.is_heading()))
+            .collect()
+    }
+
+    /// Spread undated, unfinished ("inbox") tasks over the days from `start`
+    /// onward so that no day holds more than `capacity` unfinished tasks.
+    /// Returns each inbox task's index with its proposed date, in inbox order.
+    fn plan_inbox(&self, start: NaiveDate, capacity: usize) -> Vec<(usize, NaiveDate)> {
+        let mut plan = Vec::new();
+        if capacity == 0 {
+            return plan;
+        }
+
+        let load = |day: NaiveDate| {
+            self.tasks
+                .iter()
+                .filter(|t| t.date == Some(day) && !t.is_done() && !t.is_heading())
+                .count()
+        };
+        let mut day = start;
+        let mut used = load(day);
+        let inbox = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.date.is_none() && !t.is_done() && !t.is_heading());
+        for (idx, _) in inbox {
+            while used >= capacity {
+                let Some(next) = day.succ_opt() else {
+                    return plan;
+                };
+                day = next;
+                used = load(day);
+            }
+            plan.push((idx, day));
+            used += 1;
+        }
+        plan
+    }
+
+    fn overdue_tasks(&self, current_date: &NaiveDate, config: &Config) -> Vec<(usize, &Task)> {
+        let oldest = config
+            .overdue_window_days
+            .and_then(|days| current_date.checked_sub_signed(chrono::Duration::days(days.into())));
+        let mut overdue: Vec<(usize, &Task)> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| {
+                if let Some(task_date) = t.date {
+                    task_date < *current_date
+                        && oldest.is_none_or(|oldest| task_date >= oldest)
+                        && !t.is_done()
+                        && !t.is_heading()
+                } else {
+                    false
+                }
+            })
+            .collect();
+
+        overdue.sort_by_key(|(_, t)| (t.date, t.start_time.is_none(), t.start_time));
+        if config.overdue_sort == OverdueSort::NewestFirst {
+            overdue.reverse();
+        }
+        overdue
+    }
+}
+
+fn format_time(time: NaiveTime, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::TwentyFourHour => time.format("%H:%M").to_string(),
+        TimeFormat::TwelveHour => time.format("%-I:%M %p").to_string(),
+    }
+}
+
+/// Parses a time typed into the add/edit form. Empty input means "no time".
+/// In 12-hour mode a bare hour from 1 to 12 is rejected as ambiguous, while
+/// unambiguous 24-hour values like `0:30` or `18:00` are still accepted.
+fn parse_time_input(input: &str, format: TimeFormat) -> Result<Option<NaiveTime>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    match format {
+        TimeFormat::TwentyFourHour => NaiveTime::parse_from_str(input, "%H:%M")
+            .map(Some)
+            .map_err(|_| format!("Invalid time '{}' - use HH:MM, e.g. 14:30", input)),
+        TimeFormat::TwelveHour => {
+            let invalid = || format!("Invalid time '{}' - use e.g. 9:30 PM", input);
+            let upper = input.to_ascii_uppercase();
+            let (clock, is_pm) = if let Some(rest) = upper.strip_suffix("AM") {
+                (rest.trim(), Some(false))
+            } else if let Some(rest) = upper.strip_suffix("PM") {
+                (rest.trim(), Some(true))
+            } else {
+                (upper.as_str(), None)
+            };
+
+            let (hour, minute) = match clock.split_once(':') {
+                Some((h, m)) => (h.parse::<u32>().ok(), m.parse::<u32>().ok()),
+                None => (clock.parse::<u32>().ok(), Some(0)),
+            };
+            let (Some(hour), Some(minute)) = (hour, minute) else {
+                return Err(invalid());
+            };
+
+            let hour = match is_pm {
+                Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+                Some(_) => return Err(invalid()),
+                None if hour == 0 || (13..=23).contains(&hour) => hour,
+                None => return Err(format!("Ambiguous time '{}' - add AM or PM", input)),
+            };
+
+            NaiveTime::from_hms_opt(hour, minute, 0)
+                .map(Some)
+                .ok_or_else(invalid)
+        }
+    }
+}
+
+/// Parses the form's date field: `YYYY-MM-DD`, or `+N`/`-N` days relative to
+/// `today`. Empty input leaves the task undated.
+fn parse_date_input(input: &str, today: NaiveDate) -> Result<Option<NaiveDate>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    parse_natural_date(input, today)
+        .map(Some)
+        .ok_or_else(|| format!("Invalid date '{}' - use YYYY-MM-DD, +N, tomorrow or a weekday", input))
+}
+
+/// Longest text the Date field takes, enough for "next wednesday".
+const MAX_DATE_INPUT_LEN: usize = 16;
+
+/// Characters that can appear in a date accepted by `parse_natural_date`.
+fn is_date_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | ' ')
+}
+
+/// Understands `YYYY-MM-DD`, `today`, `tomorrow`, `yesterday`, `+N`/`-N` days
+/// and weekday names (`fri`, `next monday`), which mean the next such day
+/// after today.
+fn parse_natural_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_ascii_lowercase();
+    let offset = |days: i64| today.checked_add_signed(chrono::Duration::days(days));
+
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" | "tmr" => return offset(1),
+        "yesterday" => return offset(-1),
+        _ => {}
+    }
+    if input.starts_with(['+', '-']) {
+        return offset(input.parse().ok()?);
+    }
+
+    let day_name = input.strip_prefix("next ").unwrap_or(&input).trim();
+    if let Ok(weekday) = day_name.parse::<Weekday>() {
+        let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        return offset(if ahead == 0 { 7 } else { ahead as i64 });
+    }
+
+    NaiveDate::parse_from_str(&input, "%Y-%m-%d").ok()
+}
+
+/// Gaps between the timed tasks of a day, within working hours. A task
+/// without an end time only marks its start.
+fn free_slots(tasks: &[&Task], work_start: NaiveTime, work_end: NaiveTime) -> Vec<(NaiveTime, NaiveTime)> {
+    let mut busy: Vec<(NaiveTime, NaiveTime)> = tasks
+        .iter()
+        .filter(|t| !t.is_heading())
+        .filter_map(|t| t.start_time.map(|start| (start, t.end_time.unwrap_or(start).max(start))))
+        .collect();
+    busy.sort();
+
+    let mut slots = Vec::new();
+    let mut free_from = work_start;
+    for (start, end) in busy {
+        let start = start.clamp(work_start, work_end);
+        if start > free_from {
+            slots.push((free_from, start));
+        }
+        free_from = free_from.max(end.min(work_end));
+    }
+    if free_from < work_end {
+        slots.push((free_from, work_end));
+    }
+    slots
+}
+
+/// Parses a duration like `30m`, `1h` or `1h30m` (a bare number means
+/// minutes) into minutes.
+fn parse_duration_minutes(input: &str) -> Option<u32> {
+    let input = input.trim().to_ascii_lowercase();
+    let (hours, rest) = match input.split_once('h') {
+        Some((hours, rest)) => (hours.trim().parse::<u32>().ok()?, rest),
+        None => (0, input.as_str()),
+    };
+    let rest = rest.trim().trim_end_matches('m').trim();
+    let minutes = if rest.is_empty() { 0 } else { rest.parse::<u32>().ok()? };
+    hours.checked_mul(60)?.checked_add(minutes)
+}
+
+/// Parses a reminder lead time like `30m`, `1h` or `1h30m` into minutes;
+/// empty means "use the default".
+fn parse_reminder(input: &str) -> Result<Option<u32>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    parse_duration_minutes(input)
+        .map(Some)
+        .ok_or_else(|| format!("Invalid reminder '{}' - use e.g. 10m, 1h or 1h30m", input))
+}
+
+/// Parses the End field, which besides a time accepts a duration after the
+/// start such as `+30`, `+1h` or `+1h30m`.
+fn parse_end_time_input(
+    input: &str,
+    start: Option<NaiveTime>,
+    format: TimeFormat,
+) -> Result<Option<NaiveTime>, String> {
+    let Some(duration) = input.trim().strip_prefix('+') else {
+        return parse_time_input(input, format);
+    };
+    let start = start.ok_or_else(|| "Set a start time to give the end as +duration".to_string())?;
+    let minutes = parse_duration_minutes(duration)
+        .ok_or_else(|| format!("Invalid duration '+{}' - use e.g. +30, +1h or +1h30m", duration.trim()))?;
+    let (end, wrapped) = start.overflowing_add_signed(chrono::Duration::minutes(minutes.into()));
+    if wrapped != 0 {
+        return Err("That would end after midnight".to_string());
+    }
+    Ok(Some(end))
+}
+
+/// Start `reminder_command` in the background for a task starting at
+/// `start`. The task's text is passed as `$1` and `$KEEP_TASK`, its start
+/// time as `$KEEP_START`; output is discarded so it can't garble the screen.
+fn run_reminder_command(command: &str, content: &str, start: NaiveTime) -> io::Result<()> {
+    let mut child = std::process::Command::new("sh")
+        .args(["-c", command, "keep", content])
+        .env("KEEP_TASK", content)
+        .env("KEEP_START", start.format("%H:%M").to_string())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    // Reap it once it exits rather than leaving a zombie behind
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// The columns of `keep export-csv`, in order.
+const CSV_COLUMNS: [&str; 5] = ["date", "start", "end", "completed", "content"];
+
+/// Tab-separated for `.tsv` files, comma-separated otherwise.
+fn csv_delimiter(path: &str) -> u8 {
+    if path.to_ascii_lowercase().ends_with(".tsv") { b'\t' } else { b',' }
+}
+
+/// A markdown heading's level: 1 for `# Title`, 2 for `## Title`, and so on.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// One row of the notes outline.
+#[derive(Debug, PartialEq)]
+struct OutlineRow {
+    line: usize,           // Line number in the notes, from 0
+    level: Option<usize>,  // Heading level, None for body text
+    folded: Option<usize>, // Lines hidden under a folded heading
+}
+
+/// The notes as an outline of sections: each heading's section runs until
+/// the next heading of the same or a higher level, so sections nest. A
+/// heading whose text is in `folded` keeps its whole section out of view.
+fn notes_outline(notes: &str, folded: &HashSet<String>) -> Vec<OutlineRow> {
+    let mut rows: Vec<OutlineRow> = Vec::new();
+    let mut fold_level = None;
+    for (line, text) in notes.split('\n').enumerate() {
+        let level = heading_level(text);
+        if let Some(outer) = fold_level {
+            if level.is_none_or(|level| level > outer) {
+                if let Some(hidden) = rows.last_mut().and_then(|row| row.folded.as_mut()) {
+                    *hidden += 1;
+                }
+                continue;
+            }
+            fold_level = None;
+        }
+        let is_folded = level.is_some() && folded.contains(text);
+        if is_folded {
+            fold_level = level;
+        }
+        rows.push(OutlineRow { line, level, folded: is_folded.then_some(0) });
+    }
+    rows
+}
+
+/// Byte range of the first case-insensitive match of `query` in `text`.
+/// ASCII lowercasing keeps byte offsets lined up with the original text.
+fn find_ignore_case(text: &str, query: &str) -> Option<std::ops::Range<usize>> {
+    let start = text.to_ascii_lowercase().find(&query.to_ascii_lowercase())?;
+    Some(start..start + query.len())
+}
+
+/// A task's text with the part matching the list filter highlighted.
+fn highlight_match<'a>(text: &'a str, query: Option<&str>) -> Vec<Span<'a>> {
+    match query.filter(|q| !q.is_empty()).and_then(|q| find_ignore_case(text, q)) {
+        Some(range) => vec![
+            Span::raw(&text[..range.start]),
+            Span::styled(&text[range.clone()], Style::default().fg(Color::Black).bg(Color::Yellow)),
+            Span::raw(&text[range.end..]),
+        ],
+        None => vec![Span::raw(text)],
+    }
+}
+
+/// Shorten text to at most `max` characters, ending in "..." when cut.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+/// A row of block characters scaled to the largest value. Zero is the
+/// lowest bar, so no data at all draws a flat line.
+fn sparkline(values: &[usize], ascii: bool) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const ASCII_BARS: [char; 8] = ['_', '.', '-', ':', '=', '+', '*', '#'];
+    let bars = if ascii { ASCII_BARS } else { BARS };
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| if v == 0 { bars[0] } else { bars[1 + v * (bars.len() - 2) / max] })
+        .collect()
+}
+
+/// The display order of tasks: by date (undated last), then by start time,
+/// tasks with a start time first (sorted), then tasks without. Without
+/// `auto_sort` a day keeps its stored order.
+fn compare_tasks(a: &Task, b: &Task, auto_sort: bool) -> std::cmp::Ordering {
+    let by_date = match (a.date, b.date) {
+        (Some(date_a), Some(date_b)) => date_a.cmp(&date_b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    };
+    if !auto_sort {
+        return by_date;
+    }
+    by_date
+        .then_with(|| match (a.start_time, b.start_time) {
+            (Some(time_a), Some(time_b)) => time_a.cmp(&time_b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        })
+        // A heading leads the tasks that share its time
+        .then_with(|| b.is_heading().cmp(&a.is_heading()))
+        .then_with(|| a.status.sort_rank().cmp(&b.status.sort_rank()))
+}
+
+/// A day's tasks as an aligned plain-text block for pasting into a chat.
+fn format_day_schedule(date: NaiveDate, tasks: &[&Task], config: &Config) -> String {
+    let time_format = config.time_format;
+    let times: Vec<String> = tasks
+        .iter()
+        .map(|t| match (t.start_time, t.end_time) {
+            (Some(start), Some(end)) => format!("{}–{}", format_time(start, time_format), format_time(end, time_format)),
+            (Some(start), None) => format_time(start, time_format),
+            _ => String::new(),
+        })
+        .collect();
+    let width = times.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+
+    let mut text = config.full_date(date);
+    for (task, time) in tasks.iter().zip(&times) {
+        text.push('\n');
+        if task.is_heading() {
+            text.push_str(&format!("── {} ──", task.title()));
+            continue;
+        }
+        let mark = match task.status {
+            Status::Todo => "[ ]",
+            Status::InProgress => "[~]",
+            Status::Done => "[x]",
+        };
+        // Pad by characters: the en dash is wider in bytes than on screen
+        let padding = " ".repeat(width - time.chars().count());
+        text.push_str(format!("{} {}{}  {}", mark, time, padding, task.title()).trim_end());
+    }
+    text
+}
+
+/// Put text on the system clipboard with the OSC 52 escape sequence, which
+/// most terminals (including over SSH and in tmux) understand.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    use io::Write;
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// A length of time like `1h30m`, `2h` or `45m`.
+fn format_duration(duration: chrono::Duration) -> String {
+    let (hours, minutes) = (duration.num_hours(), duration.num_minutes() % 60);
+    match (hours, minutes) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
+
+/// A link that shows a task's location: the location itself when it's
+/// already a map link, otherwise an OpenStreetMap search for it.
+fn map_url(location: &str) -> String {
+    let location = location.trim();
+    if ["http://", "https://", "geo:"].iter().any(|scheme| location.starts_with(scheme)) {
+        return location.to_string();
+    }
+    let query: String = location
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!("https://www.openstreetmap.org/search?query={}", query)
+}
+
+/// Every `http://` or `https://` link in a piece of text, with trailing
+/// punctuation from the surrounding sentence trimmed off.
+fn extract_urls(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("https://").or_else(|| word.find("http://"))?;
+            let url = word[start..].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '>', '"', '\'']);
+            Some(url.to_string())
+        })
+        .collect()
+}
+
+/// Sidebar color for an overdue task, shifting from soft orange for a day or
+/// two late to bright red once it's a week or more behind.
+fn overdue_color(days_late: i64) -> Color {
+    match days_late {
+        ..=2 => Color::Rgb(255, 170, 60),
+        3..=6 => Color::Rgb(255, 110, 40),
+        _ => Color::Rgb(255, 40, 40),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BulkOp {
+    RollOverOverdue,
+    ClearCompleted,
+    ScheduleInbox,
+    DeleteTasks,
+    CompleteAll,
+    ReopenAll,
+}
+
+/// A bulk operation waiting for confirmation, along with the exact tasks it
+/// will touch so they can be previewed first.
+struct BulkPreview {
+    op: BulkOp,
+    targets: Vec<usize>,
+    planned: Vec<NaiveDate>, // Proposed date for each target when scheduling the inbox
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchHit {
+    Task(usize),
+    Note(usize), // Byte offset of the match in the notes
+}
+
+/// The end-of-day review: a day's unfinished tasks, decided on one at a time.
+struct Review {
+    date: NaiveDate,
+    queue: Vec<u64>, // Ids of the tasks still to decide, in list order
+    total: usize,
+    kept: usize,
+    moved: usize,
+    deleted: usize,
+    done: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReviewDecision {
+    Keep,
+    Tomorrow,
+    Delete,
+    Done,
+}
+
+struct Search {
+    query: String,
+    include_notes: bool,
+    hits: Vec<SearchHit>,
+    selected: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PromptKind {
+    Snooze(usize), // Task index to push forward
+    ConfirmQuit,   // The last save failed; quitting would lose changes
+    GoToWeek,      // ISO week number to jump to
+    LogTime(usize), // Task index to log time spent against
+    Filter,         // Narrows the task list as it's typed
+}
+
+/// A one-line question asked in the footer, e.g. how long to snooze for.
+struct Prompt {
+    kind: PromptKind,
+    buffer: String,
+}
+
+/// The Monday of an ISO week typed as `23`, `w23` or `2026-W05`. A bare
+/// week number is in the ISO year of `current`, which near New Year can
+/// differ from its calendar year.
+fn parse_iso_week(input: &str, current: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_ascii_lowercase();
+    let (year, week) = match input.split_once('-') {
+        Some((year, week)) => (year.trim().parse().ok()?, week),
+        None => (current.iso_week().year(), input.as_str()),
+    };
+    let week = week.trim().trim_start_matches('w').parse().ok()?;
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+}
+
+/// Parses logged time like `25`, `+1h30m` or `-10` (to take back a
+/// mistake) into signed minutes.
+fn parse_logged_time(input: &str) -> Option<i64> {
+    let input = input.trim();
+    let (sign, amount) = match input.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, input.strip_prefix('+').unwrap_or(input)),
+    };
+    let minutes = parse_duration_minutes(amount).filter(|&m| m > 0)?;
+    Some(sign * i64::from(minutes))
+}
+
+/// Parses a snooze length like `1d`, `3d` or `1w` (a bare number means days).
+fn parse_snooze(input: &str) -> Option<i64> {
+    let input = input.trim().to_ascii_lowercase();
+    let (amount, unit_days) = if let Some(n) = input.strip_suffix('w') {
+        (n, 7)
+    } else if let Some(n) = input.strip_suffix('d') {
+        (n, 1)
+    } else {
+        (input.as_str(), 1)
+    };
+    let amount: i64 = amount.trim().parse().ok()?;
+    (amount > 0).then_some(amount * unit_days)
+}
+
+struct App {
+    data: AppData,
+    config: Config,
+    current_date: NaiveDate,
+    selected_task: usize,
+    input_mode: bool,
+    input_buffer: String,
+    input_cursor: usize, // Byte offset of the cursor in input_buffer
+    suggestion_dismissed: bool, // Esc hid the completion until the text changes
+    start_time_buffer: String,
+    end_time_buffer: String,
+    date_buffer: String,
+    label_input: Option<String>,
+    reminder_buffer: String,
+    location_buffer: String,
+    blocked_by_input: Option<u64>,
+    time_input_field: usize, // 0 = task, 1 = start_time, 2 = end_time, 3 = date, 4 = label, 5 = reminder, 6 = location, 7 = blocked by
+    editing_task_idx: Option<usize>, // None = adding new task, Some(idx) = editing task
+    input_kind: TaskKind, // Whether the form creates a task or a heading
+    notes_buffer: String,
+    notes_cursor: usize, // Cursor position in notes buffer
+    notes_outline: Option<usize>, // Selected row while the notes are shown as a foldable outline
+    notes_folded: HashSet<String>, // Headings folded in the outline, by their text
+    notes_edited_at: Option<Instant>, // Last key press in the notes editor
+    edit_notes_externally: bool, // Hand the notes to $EDITOR before the next redraw
+    done_at: HashMap<u64, Instant>, // When tasks were completed this session, by task id
+    reminded: HashSet<(String, NaiveDate, NaiveTime)>, // Tasks already reminded about this session
+    ring_bell: bool, // Ring the terminal bell once the next frame is drawn
+    show_done: bool, // Whether the "Completed" section is unfolded
+    time_filter: TimeFilter,
+    list_filter: Option<String>, // Only list tasks containing this, set with `&`
+    should_quit: bool,
+    last_save_error: Option<String>, // Set while the most recent save has failed
+    read_only: bool, // --read-only: browse without changing anything
+    today_only: bool, // --today: stay on today, without other days or the All/Week views
+    view_mode: ViewMode,
+    status_message: Option<String>,
+    sidebar_focused: bool, // Keyboard focus is in the overdue sidebar
+    overdue_selected: usize,
+    bulk_preview: Option<BulkPreview>,
+    link_picker: Option<Vec<String>>, // Links to choose from when a task has several
+    free_slots: Option<Vec<(NaiveTime, NaiveTime)>>, // Open gaps in the day being shown
+    search: Option<Search>,
+    last_search: Option<Search>, // The search `n`/`N` step through after it's closed
+    template_picker: Option<usize>, // Selected template while the picker is open
+    date_picker: Option<usize>, // Selected row of the jump-to-date list while it's open
+    trash_picker: Option<usize>, // Selected row of the trash, newest first, while it's open
+    show_about: bool, // The About popup with the version and recent changes
+    review: Option<Review>, // The end-of-day review, while it's running
+    pending_key: Option<(char, Instant)>, // First key of a vim-style sequence
+    pending_count: Option<usize>, // Numeric prefix typed before a motion, e.g. the 5 in `5j`
+    prompt: Option<Prompt>,
+    task_area: Rect, // Where the task table was last drawn, for mapping mouse clicks
+}
+
+impl App {
+    /// Load the app state. On the very first run `sample` starts it off
+    /// with a few example tasks instead of an empty list.
+    fn new(sample: bool) -> io::Result<Self> {
+        let config = Config::load();
+        let first_run = AppData::is_first_run(config.storage_format);
+        let today = config.today();
+        let mut data = if sample && first_run {
+            AppData::sample(today)
+        } else {
+            AppData::load(config.storage_format)?
+        };
+        if let Some(days) = config.trash_days {
+            data.expire_trash(Local::now().naive_local(), days);
+        }
+        let notes_buffer = data.notes.clone();
+        let notes_cursor = notes_buffer.len();
+        // Auto-hiding finished tasks only makes sense with their section folded
+        let show_done = config.hide_done_after_secs.is_none();
+        Ok(Self {
+            data,
+            config,
+            current_date: today,
+            selected_task: 0,
+            input_mode: false,
+            input_buffer: String::new(),
+            input_cursor: 0,
+            suggestion_dismissed: false,
+            start_time_buffer: String::new(),
+            end_time_buffer: String::new(),
+            date_buffer: String::new(),
+            label_input: None,
+            reminder_buffer: String::new(),
+            location_buffer: String::new(),
+            blocked_by_input: None,
+            time_input_field: 0,
+            editing_task_idx: None,
+            input_kind: TaskKind::Task,
+            notes_buffer,
             notes_cursor,
+            notes_outline: None,
+            notes_folded: HashSet::new(),
+            notes_edited_at: None,
+            edit_notes_externally: false,
+            done_at: HashMap::new(),
+            reminded: HashSet::new(),
+            ring_bell: false,
+            show_done,
+            time_filter: TimeFilter::All,
+            list_filter: None,
             should_quit: false,
+            last_save_error: None,
+            read_only: false,
+            today_only: false,
             view_mode: ViewMode::Scheduled,
+            status_message: (sample && first_run)
+                .then(|| "Welcome! These example tasks are yours to complete or delete".to_string()),
+            sidebar_focused: false,
+            overdue_selected: 0,
+            bulk_preview: None,
+            link_picker: None,
+            free_slots: None,
+            search: None,
+            last_search: None,
+            template_picker: None,
+            date_picker: None,
+            trash_picker: None,
+            show_about: false,
+            review: None,
+            pending_key: None,
+            pending_count: None,
+            prompt: None,
+            task_area: Rect::default(),
         })
     }
 
-    fn next_day(&mut self) {
-        self.current_date = self.current_date.succ_opt().unwrap_or(self.current_date);
-        self.selected_task = 0;
+    fn next_day(&mut self) {
+        self.current_date = self.current_date.succ_opt().unwrap_or(self.current_date);
+        self.selected_task = 0;
+    }
+
+    fn prev_day(&mut self) {
+        self.current_date = self.current_date.pred_opt().unwrap_or(self.current_date);
+        self.selected_task = 0;
+    }
+
+    /// Jump forward to the nearest later date that has at least one task.
+    fn next_busy_day(&mut self) {
+        let next = self
+            .data
+            .tasks
+            .iter()
+            .filter_map(|t| t.date)
+            .filter(|d| *d > self.current_date)
+            .min();
+        if let Some(date) = next {
+            self.current_date = date;
+            self.selected_task = 0;
+        }
+    }
+
+    /// Jump back to the nearest earlier date that has at least one task.
+    fn prev_busy_day(&mut self) {
+        let prev = self
+            .data
+            .tasks
+            .iter()
+            .filter_map(|t| t.date)
+            .filter(|d| *d < self.current_date)
+            .max();
+        if let Some(date) = prev {
+            self.current_date = date;
+            self.selected_task = 0;
+        }
+    }
+
+    /// Tasks in the current view, leaving out the "Completed" section while
+    /// it's folded.
+    fn current_tasks(&self) -> Vec<(usize, &Task)> {
+        let mut tasks = self.listed_tasks();
+        if !self.show_done || self.config.completed_placement == CompletedPlacement::Hidden {
+            tasks.retain(|&(_, task)| !self.in_completed_section(task));
+        }
+        tasks
+    }
+
+    /// Whether a finished task has moved down into the "Completed" section
+    /// (or out of sight, with `completed_placement` set to hidden). With
+    /// `hide_done_after_secs` set, tasks finished this session linger in
+    /// place that long first.
+    fn in_completed_section(&self, task: &Task) -> bool {
+        if !task.is_done() || task.is_heading() || self.config.completed_placement == CompletedPlacement::InPlace {
+            return false;
+        }
+        match self.config.hide_done_after_secs {
+            Some(secs) => self.done_at.get(&task.id).is_none_or(|at| at.elapsed() >= Duration::from_secs(secs)),
+            None => true,
+        }
+    }
+
+    /// The position in the task list shown on a table row (counting from the
+    /// first row below the header), skipping the "Completed" section header.
+    fn task_at_row(&self, row: usize) -> Option<usize> {
+        let tasks = self.current_tasks();
+        let section = (self.completed_count() > 0).then(|| {
+            tasks
+                .iter()
+                .position(|&(_, task)| self.in_completed_section(task))
+                .unwrap_or(tasks.len())
+        });
+        // Tasks with several lines take several rows
+        let mut top = 0;
+        for (position, (_, task)) in tasks.iter().enumerate() {
+            if section == Some(position) {
+                if row == top {
+                    return None;
+                }
+                top += 1;
+            }
+            let height = if self.config.one_line_rows { 1 } else { task.row_height() };
+            if row < top + height {
+                return Some(position);
+            }
+            top += height;
+        }
+        None
+    }
+
+    /// Tasks in the "Completed" section, which only has a header row when
+    /// finished tasks gather at the bottom.
+    fn completed_count(&self) -> usize {
+        if self.config.completed_placement != CompletedPlacement::Bottom {
+            return 0;
+        }
+        self.listed_tasks()
+            .into_iter()
+            .filter(|&(_, task)| self.in_completed_section(task))
+            .count()
+    }
+
+    /// Every task in the current view, sorted for display.
+    fn listed_tasks(&self) -> Vec<(usize, &Task)> {
+        let mut tasks = match self.view_mode {
+            ViewMode::Scheduled => self.data.tasks_for_date(&self.current_date),
+            ViewMode::All => self.data.tasks.iter().enumerate().collect(),
+            ViewMode::Week => self.data.tasks_for_week(&self.current_date),
+            ViewMode::Notes => Vec::new(), // No tasks in notes view
+        };
+        tasks.retain(|(_, task)| self.time_filter.matches(task));
+        if let Some(filter) = &self.list_filter {
+            tasks.retain(|(_, task)| find_ignore_case(&task.content, filter).is_some());
+        }
+
+        tasks.sort_by(|a, b| compare_tasks(a.1, b.1, self.config.auto_sort));
+        // The day's focus leads, whatever its time
+        if self.view_mode == ViewMode::Scheduled {
+            tasks.sort_by_key(|(_, task)| !(task.focus && task.date == Some(self.current_date)));
+        }
+        // Finished tasks gather at the bottom, in the same order
+        tasks.sort_by_key(|&(_, task)| self.in_completed_section(task));
+
+        tasks
+    }
+
+    fn next_task(&mut self) {
+        let tasks = self.current_tasks();
+        if self.selected_task + 1 < tasks.len() {
+            self.selected_task += 1;
+        } else if self.config.wrap_navigation {
+            self.selected_task = 0;
+        }
+    }
+
+    fn prev_task(&mut self) {
+        let tasks = self.current_tasks();
+        if self.selected_task > 0 {
+            self.selected_task -= 1;
+        } else if self.config.wrap_navigation {
+            self.selected_task = tasks.len().saturating_sub(1);
+        }
+    }
+
+    fn select_first_task(&mut self) {
+        self.selected_task = 0;
+    }
+
+    fn select_last_task(&mut self) {
+        self.selected_task = self.current_tasks().len().saturating_sub(1);
+    }
+
+    fn toggle_task(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) {
+            let task = &mut self.data.tasks[idx];
+            task.set_status(if task.is_done() { Status::Todo } else { Status::Done });
+            let done = task.is_done();
+            self.note_completion(idx);
+            self.persist();
+            if done && self.config.advance_on_complete {
+                self.select_next_unfinished(idx, self.selected_task);
+            } else {
+                self.select_task_index(idx);
+            }
+        }
+    }
+
+    /// Select the first unfinished task from `position` on (where the task
+    /// at `idx` was just finished), wrapping around to the top, or stay on
+    /// `idx` when everything is done.
+    fn select_next_unfinished(&mut self, idx: usize, position: usize) {
+        let tasks = self.current_tasks();
+        let unfinished = |&(i, task): &(usize, &Task)| i != idx && !task.is_done() && !task.is_heading();
+        let next = tasks
+            .iter()
+            .skip(position)
+            .position(unfinished)
+            .map(|offset| position + offset)
+            .or_else(|| tasks.iter().position(unfinished));
+        match next {
+            Some(next) => self.selected_task = next,
+            None => self.select_task_index(idx),
+        }
+    }
+
+    fn cycle_status(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) {
+            let task = &mut self.data.tasks[idx];
+            task.set_status(task.status.next());
+            self.note_completion(idx);
+            self.persist();
+            self.select_task_index(idx);
+        }
+    }
+
+    /// Push the selected task a day later (or earlier); an undated task
+    /// lands on the day after (or before) today.
+    fn shift_task_date(&mut self, days: i64) {
+        let tasks = self.current_tasks();
+        let Some(&(idx, _)) = tasks.get(self.selected_task) else {
+            return;
+        };
+        let from = self.data.tasks[idx].date.unwrap_or(self.config.today());
+        let Some(to) = from.checked_add_signed(chrono::Duration::days(days)) else {
+            return;
+        };
+        self.data.tasks[idx].move_to(Some(to));
+        self.status_message = Some(format!("Moved to {}", to.format("%a, %b %d")));
+        self.persist();
+        self.select_task_index(idx);
+    }
+
+    /// Keep the cursor on the task at `idx` in `data.tasks` after the list
+    /// has re-sorted around it.
+    fn select_task_index(&mut self, idx: usize) {
+        match self.current_tasks().iter().position(|&(i, _)| i == idx) {
+            Some(position) => self.selected_task = position,
+            None => self.clamp_selection(),
+        }
+    }
+
+    /// Swap the selected task with its neighbor above or below, for lists
+    /// that aren't sorted automatically.
+    fn move_task(&mut self, down: bool) {
+        if self.config.auto_sort {
+            self.status_message = Some("Tasks are sorted by time - set auto_sort to false to reorder them".to_string());
+            return;
+        }
+        let tasks = self.current_tasks();
+        let Some(&(idx, task)) = tasks.get(self.selected_task) else {
+            return;
+        };
+        let neighbor = if down {
+            tasks.get(self.selected_task + 1)
+        } else {
+            self.selected_task.checked_sub(1).and_then(|position| tasks.get(position))
+        };
+        // Stay within the day, and out of (or inside) the Completed section
+        let Some(&(other, _)) = neighbor.filter(|&&(_, other_task)| {
+            other_task.date == task.date
+                && self.in_completed_section(other_task) == self.in_completed_section(task)
+        }) else {
+            return;
+        };
+
+        self.data.tasks.swap(idx, other);
+        self.last_search = None;
+        self.persist();
+        self.select_task_index(other);
+    }
+
+    /// Start (or cancel) the countdown before a finished task collapses.
+    fn note_completion(&mut self, idx: usize) {
+        let task = &self.data.tasks[idx];
+        if task.is_done() {
+            self.done_at.insert(task.id, Instant::now());
+        } else {
+            self.done_at.remove(&task.id);
+        }
+    }
+
+    fn cycle_time_filter(&mut self) {
+        self.time_filter = self.time_filter.next();
+        self.clamp_selection();
+    }
+
+    fn toggle_show_done(&mut self) {
+        self.show_done = !self.show_done;
+        self.clamp_selection();
+    }
+
+    /// Make the selected task its day's focus, taking over from any other.
+    fn toggle_focus(&mut self) {
+        let tasks = self.current_tasks();
+        let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) else {
+            return;
+        };
+        let focus = !self.data.tasks[idx].focus;
+        let date = self.data.tasks[idx].date;
+        for task in self.data.tasks.iter_mut().filter(|t| t.date == date) {
+            task.focus = false;
+        }
+        self.data.tasks[idx].focus = focus;
+        self.status_message = Some(match (focus, date) {
+            (true, Some(date)) => format!("Focus for {}", date.format("%a, %b %d")),
+            (true, None) => "Focus for undated tasks".to_string(),
+            (false, _) => "Focus cleared".to_string(),
+        });
+        self.persist();
+        self.select_task_index(idx);
+    }
+
+    fn toggle_pin(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) {
+            self.data.tasks[idx].pinned = !self.data.tasks[idx].pinned;
+            self.persist();
+        }
+    }
+
+    fn start_edit_task(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task) {
+            // Clone the task data before dropping the borrow
+            let task = self.data.tasks[idx].clone();
+            let time_format = self.config.time_format;
+
+            self.input_buffer = task.content;
+            self.input_cursor = self.input_buffer.len();
+            self.suggestion_dismissed = false;
+            self.start_time_buffer = task
+                .start_time
+                .map(|t| format_time(t, time_format))
+                .unwrap_or_default();
+            self.end_time_buffer = task
+                .end_time
+                .map(|t| format_time(t, time_format))
+                .unwrap_or_default();
+            self.date_buffer = task
+                .date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            self.label_input = task.label;
+            self.reminder_buffer = task
+                .reminder_minutes
+                .map(|m| format_duration(chrono::Duration::minutes(m as i64)))
+                .unwrap_or_default();
+            self.location_buffer = task.location.unwrap_or_default();
+            self.blocked_by_input = task.blocked_by;
+            self.editing_task_idx = Some(idx);
+            self.input_kind = task.kind;
+            self.input_mode = true;
+            self.time_input_field = 0;
+        }
+    }
+
+    fn start_add_task(&mut self) {
+        self.input_buffer.clear();
+        self.input_cursor = 0;
+        self.suggestion_dismissed = false;
+        self.start_time_buffer.clear();
+        self.end_time_buffer.clear();
+        self.date_buffer = self.current_date.format("%Y-%m-%d").to_string();
+        self.label_input = None;
+        self.reminder_buffer.clear();
+        self.location_buffer.clear();
+        self.blocked_by_input = None;
+        self.editing_task_idx = None;
+        self.input_kind = TaskKind::Task;
+        self.input_mode = true;
+        self.time_input_field = 0;
+    }
+
+    fn start_add_heading(&mut self) {
+        self.start_add_task();
+        self.input_kind = TaskKind::Heading;
+    }
+
+    /// Open the add form pre-filled from a template, dated for the current day.
+    fn start_add_from_template(&mut self, template_idx: usize) {
+        let Some(template) = self.data.templates.get(template_idx).cloned() else {
+            return;
+        };
+        let time_format = self.config.time_format;
+
+        self.start_add_task();
+        self.input_buffer = template.content;
+        self.input_cursor = self.input_buffer.len();
+        self.start_time_buffer = template
+            .start_time
+            .map(|t| format_time(t, time_format))
+            .unwrap_or_default();
+        self.end_time_buffer = template
+            .end_time
+            .map(|t| format_time(t, time_format))
+            .unwrap_or_default();
+    }
+
+    /// Open the add form filled in from the most recently created task.
+    fn start_add_like_last(&mut self) {
+        let Some(last) = self
+            .data
+            .tasks
+            .iter()
+            .filter(|t| !t.is_heading() && t.created_at.is_some())
+            .max_by_key(|t| t.created_at)
+            .cloned()
+        else {
+            self.status_message = Some("No recently added task to repeat".to_string());
+            return;
+        };
+        let time_format = self.config.time_format;
+
+        self.start_add_task();
+        self.input_buffer = last.content;
+        self.input_cursor = self.input_buffer.len();
+        self.start_time_buffer = last.start_time.map(|t| format_time(t, time_format)).unwrap_or_default();
+        self.end_time_buffer = last.end_time.map(|t| format_time(t, time_format)).unwrap_or_default();
+        self.label_input = last.label;
+        self.location_buffer = last.location.unwrap_or_default();
+    }
+
+    /// The form's location, if one was entered.
+    fn location_input(&self) -> Option<String> {
+        let location = self.location_buffer.trim();
+        (!location.is_empty()).then(|| location.to_string())
+    }
+
+    fn save_selected_as_template(&mut self) {
+        let tasks = self.current_tasks();
+        let Some(&(_, task)) = tasks.get(self.selected_task) else {
+            self.status_message = Some("Select a task to save it as a template".to_string());
+            return;
+        };
+
+        let template = Template {
+            name: task.content.clone(),
+            content: task.content.clone(),
+            start_time: task.start_time,
+            end_time: task.end_time,
+            routine: false,
+        };
+        self.status_message = Some(format!("Saved template '{}'", template.name));
+        self.data.templates.push(template);
+        self.persist();
+    }
+
+    fn toggle_routine(&mut self, template_idx: usize) {
+        if let Some(template) = self.data.templates.get_mut(template_idx) {
+            template.routine = !template.routine;
+            self.persist();
+        }
+    }
+
+    /// Add every routine template to the current day, skipping any that are
+    /// already there.
+    fn seed_routine(&mut self) {
+        if !self.data.templates.iter().any(|t| t.routine) {
+            self.status_message = Some("No routine templates - mark some with r in the template list (T)".to_string());
+            return;
+        }
+
+        let date = self.current_date;
+        let routine: Vec<Template> = self
+            .data
+            .templates
+            .iter()
+            .filter(|t| t.routine)
+            .filter(|t| {
+                !self.data.tasks.iter().any(|task| task.date == Some(date) && task.content == t.content)
+            })
+            .cloned()
+            .collect();
+
+        let added = routine.len();
+        self.data.tasks.extend(routine.into_iter().map(|template| Task {
+            id: 0,
+            content: template.content,
+            status: Status::Todo,
+            date: Some(date),
+            start_time: template.start_time,
+            end_time: template.end_time,
+            pinned: false,
+            kind: TaskKind::Task,
+            label: None,
+            reminder_minutes: None,
+            completed_at: None,
+            created_at: Some(Local::now().naive_local()),
+            logged_minutes: 0,
+            location: None,
+            focus: false,
+            blocked_by: None,
+        }));
+        self.data.assign_ids();
+        self.status_message = Some(format!("Added {} routine task(s) to {}", added, date.format("%a, %b %d")));
+        self.persist();
+    }
+
+    fn delete_template(&mut self, template_idx: usize) {
+        if template_idx < self.data.templates.len() {
+            let removed = self.data.templates.remove(template_idx);
+            self.status_message = Some(format!("Deleted template '{}'", removed.name));
+            self.persist();
+        }
+        if let Some(selected) = self.template_picker.as_mut() {
+            *selected = (*selected).min(self.data.templates.len().saturating_sub(1));
+        }
+    }
+
+    /// Step the form's label through "none" and each configured label.
+    fn cycle_label(&mut self, forward: bool) {
+        let mut options: Vec<Option<String>> = vec![None];
+        options.extend(self.config.labels.keys().cloned().map(Some));
+
+        let current = options.iter().position(|l| *l == self.label_input).unwrap_or(0);
+        let next = if forward {
+            (current + 1) % options.len()
+        } else {
+            (current + options.len() - 1) % options.len()
+        };
+        self.label_input = options[next].clone();
+    }
+
+    /// Step the form's "after" field through "none" and the other unfinished
+    /// tasks on the form's date.
+    fn cycle_blocker(&mut self, forward: bool) {
+        let date = parse_date_input(&self.date_buffer, self.config.today()).unwrap_or(Some(self.current_date));
+        let editing = self.editing_task_idx.map(|idx| self.data.tasks[idx].id);
+        let mut options: Vec<Option<u64>> = vec![None];
+        options.extend(
+            self.data
+                .tasks
+                .iter()
+                .filter(|t| t.date == date && !t.is_done() && !t.is_heading() && Some(t.id) != editing)
+                .map(|t| Some(t.id)),
+        );
+
+        let current = options.iter().position(|id| *id == self.blocked_by_input).unwrap_or(0);
+        let next = if forward {
+            (current + 1) % options.len()
+        } else {
+            (current + options.len() - 1) % options.len()
+        };
+        self.blocked_by_input = options[next];
+    }
+
+    /// The rest of the most recently added task whose content starts with
+    /// what's been typed so far, offered as ghost text at the end of the
+    /// task field.
+    fn content_suggestion(&self) -> Option<&str> {
+        let typed = self.input_buffer.as_str();
+        if !self.config.autocomplete
+            || self.suggestion_dismissed
+            || self.time_input_field != 0
+            || typed.trim().is_empty()
+            || self.input_cursor != typed.len()
+        {
+            return None;
+        }
+
+        self.data
+            .tasks
+            .iter()
+            .rev()
+            .map(|t| t.content.as_str())
+            .find(|content| {
+                content.len() > typed.len()
+                    && content.get(..typed.len()).is_some_and(|p| p.eq_ignore_ascii_case(typed))
+            })
+            .map(|content| &content[typed.len()..])
+    }
+
+    fn accept_suggestion(&mut self) -> bool {
+        match self.content_suggestion().map(str::to_string) {
+            Some(rest) => {
+                self.input_buffer.push_str(&rest);
+                self.input_cursor = self.input_buffer.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn add_task(&mut self) {
+        if !self.input_buffer.trim().is_empty() {
+            let time_format = self.config.time_format;
+            let times = parse_time_input(&self.start_time_buffer, time_format).and_then(|start| {
+                parse_end_time_input(&self.end_time_buffer, start, time_format).map(|end| (start, end))
+            });
+            let parsed = times.and_then(|(start, end)| {
+                parse_date_input(&self.date_buffer, self.config.today())
+                    .map(|date| (start, end, date))
+            });
+            let parsed = parsed.and_then(|(start, end, date)| {
+                parse_reminder(&self.reminder_buffer).map(|reminder| (start, end, date, reminder))
+            });
+            let (start_time, end_time, date, reminder_minutes) = match parsed {
+                Ok(parsed) => parsed,
+                Err(message) => {
+                    // Keep the form open so the entry can be corrected
+                    self.status_message = Some(message);
+                    return;
+                }
+            };
+
+            if let Some(idx) = self.editing_task_idx {
+                // Editing existing task
+                self.data.tasks[idx].content = self.input_buffer.trim().to_string();
+                self.data.tasks[idx].start_time = start_time;
+                self.data.tasks[idx].end_time = end_time;
+                self.data.tasks[idx].move_to(date);
+                self.data.tasks[idx].label = self.label_input.take();
+                self.data.tasks[idx].reminder_minutes = reminder_minutes;
+                self.data.tasks[idx].location = self.location_input();
+                self.data.tasks[idx].blocked_by = self.blocked_by_input;
+            } else {
+                let id = self.data.next_id();
+                self.data.tasks.push(Task {
+                    id,
+                    content: self.input_buffer.trim().to_string(),
+                    status: Status::Todo,
+                    date,
+                    start_time,
+                    end_time,
+                    pinned: false,
+                    kind: self.input_kind,
+                    label: self.label_input.take(),
+                    reminder_minutes,
+                    completed_at: None,
+                    created_at: Some(Local::now().naive_local()),
+                    logged_minutes: 0,
+                    location: self.location_input(),
+                    focus: false,
+                    blocked_by: self.blocked_by_input,
+                });
+            }
+            self.persist();
+            self.input_buffer.clear();
+            self.input_cursor = 0;
+            self.start_time_buffer.clear();
+            self.end_time_buffer.clear();
+            self.date_buffer.clear();
+            self.reminder_buffer.clear();
+            self.location_buffer.clear();
+        }
+        self.input_mode = false;
+        self.time_input_field = 0;
+        self.editing_task_idx = None;
+        // An edited task may have moved to another day
+        self.clamp_selection();
+    }
+
+    /// Announce tasks starting today whose reminder lead time has been
+    /// reached, once each.
+    fn check_reminders(&mut self) {
+        let now = Local::now().naive_local();
+        let today = effective_date(now, self.config.day_boundary_hour);
+        let default = self.config.reminder_minutes;
+        let due: Vec<(String, NaiveDate, NaiveTime)> = self
+            .data
+            .tasks
+            .iter()
+            .filter(|t| !t.is_done() && !t.is_heading() && t.date == Some(today))
+            .filter_map(|t| {
+                let start = t.date?.and_time(t.start_time?);
+                let lead = chrono::Duration::minutes(t.reminder_minutes.or(default)? as i64);
+                (now >= start - lead && now < start).then(|| (t.title().to_string(), today, start.time()))
+            })
+            .filter(|key| !self.reminded.contains(key))
+            .collect();
+
+        if let Some((content, _, start)) = due.last() {
+            self.status_message = Some(format!(
+                "{}{} starts at {}",
+                self.config.glyph("⏰ ", "Reminder: "),
+                content,
+                format_time(*start, self.config.time_format)
+            ));
+            self.ring_bell = self.config.reminder_command.is_none();
+        }
+        if let Some(command) = &self.config.reminder_command {
+            for (content, _, start) in &due {
+                if let Err(err) = run_reminder_command(command, content, *start) {
+                    self.status_message = Some(format!("Couldn't run reminder_command: {}", err));
+                }
+            }
+        }
+        self.reminded.extend(due);
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.current_tasks().len();
+        self.selected_task = self.selected_task.min(len.saturating_sub(1));
+    }
+
+    fn delete_task(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task) {
+            self.remove_tasks(vec![idx]);
+            if self.config.trash_days.is_some() {
+                self.status_message = Some("Moved to the trash (X to restore)".to_string());
+            }
+            self.persist();
+            if self.selected_task > 0 {
+                self.selected_task -= 1;
+            }
+        }
+    }
+
+    /// Take tasks out of the list, into the trash when it's turned on.
+    fn remove_tasks(&mut self, mut targets: Vec<usize>) {
+        let deleted_at = Local::now().naive_local();
+        // Remove from the back so earlier indices stay valid
+        targets.sort_unstable_by(|a, b| b.cmp(a));
+        let mut removed: Vec<TrashedTask> = targets
+            .into_iter()
+            .map(|idx| TrashedTask { task: self.data.tasks.remove(idx), deleted_at })
+            .collect();
+        if self.config.trash_days.is_some() {
+            removed.reverse();
+            self.data.trash.extend(removed);
+        }
+        self.last_search = None;
+    }
+
+    /// Walk through the day's unfinished tasks, deciding on each in turn.
+    fn start_review(&mut self) {
+        let mut tasks: Vec<&Task> = self
+            .data
+            .tasks
+            .iter()
+            .filter(|t| t.date == Some(self.current_date) && !t.is_done() && !t.is_heading())
+            .collect();
+        if tasks.is_empty() {
+            self.status_message = Some(format!("Nothing left to review on {}", self.current_date.format("%a, %b %d")));
+            return;
+        }
+        tasks.sort_by(|a, b| compare_tasks(a, b, self.config.auto_sort));
+        self.review = Some(Review {
+            date: self.current_date,
+            queue: tasks.iter().map(|t| t.id).collect(),
+            total: tasks.len(),
+            kept: 0,
+            moved: 0,
+            deleted: 0,
+            done: 0,
+        });
+        self.skip_reviewed();
+    }
+
+    /// The task the review is asking about.
+    fn review_task(&self) -> Option<usize> {
+        let id = *self.review.as_ref()?.queue.first()?;
+        self.data.tasks.iter().position(|t| t.id == id)
+    }
+
+    /// Drop tasks from the front of the review that are gone or no longer
+    /// need deciding, and finish once none are left.
+    fn skip_reviewed(&mut self) {
+        while let Some(review) = &self.review {
+            let Some(&id) = review.queue.first() else {
+                self.finish_review();
+                return;
+            };
+            if self.data.tasks.iter().any(|t| t.id == id && !t.is_done() && t.date == Some(review.date)) {
+                return;
+            }
+            if let Some(review) = self.review.as_mut() {
+                review.queue.remove(0);
+            }
+        }
+    }
+
+    fn decide_review(&mut self, decision: ReviewDecision) {
+        let Some(idx) = self.review_task() else {
+            return;
+        };
+        let Some(review) = self.review.as_mut() else {
+            return;
+        };
+        review.queue.remove(0);
+        match decision {
+            ReviewDecision::Keep => review.kept += 1,
+            ReviewDecision::Tomorrow => {
+                review.moved += 1;
+                self.data.tasks[idx].move_to(review.date.succ_opt());
+            }
+            ReviewDecision::Delete => {
+                review.deleted += 1;
+                self.remove_tasks(vec![idx]);
+            }
+            ReviewDecision::Done => {
+                review.done += 1;
+                self.data.tasks[idx].set_status(Status::Done);
+                self.note_completion(idx);
+            }
+        }
+        if decision != ReviewDecision::Keep {
+            self.persist();
+        }
+        self.skip_reviewed();
+    }
+
+    /// End the review, summing up what was decided.
+    fn finish_review(&mut self) {
+        let Some(review) = self.review.take() else {
+            return;
+        };
+        let decided = review.kept + review.moved + review.deleted + review.done;
+        self.status_message = Some(format!(
+            "Reviewed {} of {}: {} done, {} moved to tomorrow, {} deleted, {} kept",
+            decided, review.total, review.done, review.moved, review.deleted, review.kept
+        ));
+        self.clamp_selection();
+    }
+
+    fn open_trash(&mut self) {
+        if self.data.trash.is_empty() {
+            self.status_message = Some(if self.config.trash_days.is_some() {
+                "The trash is empty".to_string()
+            } else {
+                "The trash is empty - set trash_days to keep deleted tasks".to_string()
+            });
+            return;
+        }
+        self.trash_picker = Some(0);
+    }
+
+    /// Position in `data.trash` of the trash list's row, which shows the
+    /// newest first.
+    fn trash_index(&self, selected: usize) -> Option<usize> {
+        self.data.trash.len().checked_sub(selected + 1)
+    }
+
+    fn restore_trashed(&mut self, selected: usize) {
+        let Some(idx) = self.trash_index(selected) else {
+            return;
+        };
+        let task = self.data.trash.remove(idx).task;
+        self.status_message = Some(match task.date {
+            Some(date) => format!("Restored '{}' to {}", task.title(), date.format("%a, %b %d")),
+            None => format!("Restored '{}'", task.title()),
+        });
+        self.data.tasks.push(task);
+        self.after_trash_change(selected);
+    }
+
+    fn purge_trashed(&mut self, selected: usize) {
+        let Some(idx) = self.trash_index(selected) else {
+            return;
+        };
+        let task = self.data.trash.remove(idx).task;
+        self.status_message = Some(format!("Deleted '{}' for good", task.title()));
+        self.after_trash_change(selected);
+    }
+
+    fn empty_trash(&mut self) {
+        let count = self.data.trash.len();
+        self.data.trash.clear();
+        self.status_message = Some(format!("Emptied the trash ({} task(s))", count));
+        self.after_trash_change(0);
+    }
+
+    fn after_trash_change(&mut self, selected: usize) {
+        let count = self.data.trash.len();
+        self.trash_picker = (count > 0).then(|| selected.min(count - 1));
+        self.persist();
+    }
+
+    /// Longest accepted time entry; 12-hour entry needs room for the AM/PM
+    /// suffix, e.g. "12:30 PM".
+    fn max_time_len(&self) -> usize {
+        match self.config.time_format {
+            TimeFormat::TwentyFourHour => 5,
+            TimeFormat::TwelveHour => 8,
+        }
+    }
+
+    fn is_time_char(&self, c: char) -> bool {
+        c.is_ascii_digit()
+            || c == ':'
+            || (self.config.time_format == TimeFormat::TwelveHour
+                && matches!(c, ' ' | 'a' | 'A' | 'p' | 'P' | 'm' | 'M'))
+    }
+
+    fn toggle_sidebar_focus(&mut self) {
+        let has_overdue = !self.data.overdue_tasks(&self.config.today(), &self.config).is_empty();
+        self.sidebar_focused = !self.sidebar_focused && has_overdue && self.config.show_sidebar;
+        self.overdue_selected = 0;
+    }
+
+    fn toggle_density(&mut self) {
+        self.config.density = match self.config.density {
+            Density::Comfortable => Density::Compact,
+            Density::Compact => Density::Comfortable,
+        };
+        self.persist();
+    }
+
+    fn toggle_compact_header(&mut self) {
+        self.config.compact_header = !self.config.compact_header;
+        self.persist();
+    }
+
+    fn toggle_one_line_rows(&mut self) {
+        self.config.one_line_rows = !self.config.one_line_rows;
+        self.persist();
+    }
+
+    /// Columns taken by the checkbox within a task row, from the table's left edge.
+    fn checkbox_columns(&self) -> std::ops::Range<u16> {
+        let width = self.config.glyph("○", "[ ]").chars().count() as u16;
+        if !self.config.one_line_rows {
+            return 0..3;
+        }
+        let date = if self.view_mode == ViewMode::All { 11 } else { 0 };
+        let start = date + self.max_time_len() as u16 + 1;
+        start..start + width
+    }
+
+    /// Open the list of dates with tasks, starting at the day being viewed
+    /// (or the next one with tasks).
+    fn open_date_picker(&mut self) {
+        let dates = self.data.task_dates();
+        if dates.is_empty() {
+            self.status_message = Some("No dated tasks yet".to_string());
+            return;
+        }
+        let selected = dates
+            .iter()
+            .position(|&(date, _, _)| date >= self.current_date)
+            .unwrap_or(dates.len() - 1);
+        self.date_picker = Some(selected);
+    }
+
+    fn jump_to_picked_date(&mut self, selected: usize) {
+        self.date_picker = None;
+        if let Some(&(date, _, _)) = self.data.task_dates().get(selected) {
+            self.current_date = date;
+            self.view_mode = ViewMode::Scheduled;
+            self.selected_task = 0;
+        }
+    }
+
+    /// Switch the notes between the raw editor and the outline, keeping
+    /// the cursor's line selected either way.
+    fn toggle_outline(&mut self) {
+        let rows = notes_outline(&self.notes_buffer, &self.notes_folded);
+        match self.notes_outline.take() {
+            Some(selected) => {
+                let line = rows.get(selected).map_or(0, |row| row.line);
+                self.notes_cursor = self.notes_buffer.split('\n').take(line).map(|l| l.len() + 1).sum();
+            }
+            None => {
+                let line = self.notes_buffer[..self.notes_cursor].matches('\n').count();
+                self.notes_outline = Some(rows.iter().rposition(|row| row.line <= line).unwrap_or(0));
+            }
+        }
+    }
+
+    /// Fold or unfold the section under the selected outline heading.
+    fn toggle_fold(&mut self, selected: usize) {
+        let rows = notes_outline(&self.notes_buffer, &self.notes_folded);
+        let Some(row) = rows.get(selected).filter(|row| row.level.is_some()) else {
+            return;
+        };
+        let heading = self.notes_buffer.split('\n').nth(row.line).unwrap_or_default().to_string();
+        if !self.notes_folded.remove(&heading) {
+            self.notes_folded.insert(heading);
+        }
+    }
+
+    fn toggle_line_numbers(&mut self) {
+        self.config.notes_line_numbers = !self.config.notes_line_numbers;
+        self.persist();
+    }
+
+    fn toggle_week_buckets(&mut self) {
+        self.config.week_buckets = !self.config.week_buckets;
+        self.persist();
+    }
+
+    fn shift_week(&mut self, forward: bool) {
+        let days = if forward { 7 } else { -7 };
+        self.current_date = self
+            .current_date
+            .checked_add_signed(chrono::Duration::days(days))
+            .unwrap_or(self.current_date);
+        self.selected_task = 0;
+    }
+
+    fn resize_sidebar(&mut self, delta: i16) {
+        const MIN_WIDTH: u16 = 20;
+        const MAX_WIDTH: u16 = 80;
+
+        let width = self.config.sidebar_width.saturating_add_signed(delta);
+        self.config.sidebar_width = width.clamp(MIN_WIDTH, MAX_WIDTH);
+        self.status_message = Some(format!("Sidebar width: {}", self.config.sidebar_width));
+        self.persist();
+    }
+
+    fn toggle_sidebar(&mut self) {
+        self.config.show_sidebar = !self.config.show_sidebar;
+        self.sidebar_focused = false;
+        self.persist();
+    }
+
+    fn next_overdue(&mut self) {
+        let count = self.data.overdue_tasks(&self.config.today(), &self.config).len();
+        if count > 0 {
+            self.overdue_selected = (self.overdue_selected + 1) % count;
+        }
+    }
+
+    fn prev_overdue(&mut self) {
+        let count = self.data.overdue_tasks(&self.config.today(), &self.config).len();
+        if count > 0 {
+            self.overdue_selected = if self.overdue_selected == 0 {
+                count - 1
+            } else {
+                self.overdue_selected - 1
+            };
+        }
+    }
+
+    fn complete_overdue(&mut self) {
+        let overdue = self.data.overdue_tasks(&self.config.today(), &self.config);
+        if let Some(&(idx, _)) = overdue.get(self.overdue_selected) {
+            self.data.tasks[idx].set_status(Status::Done);
+            self.persist();
+            self.clamp_overdue_selection();
+        }
+    }
+
+    /// Move the selected overdue task onto today.
+    fn roll_over_overdue(&mut self) {
+        let today = self.config.today();
+        let overdue = self.data.overdue_tasks(&today, &self.config);
+        if let Some(&(idx, _)) = overdue.get(self.overdue_selected) {
+            self.data.tasks[idx].move_to(Some(today));
+            self.persist();
+            self.clamp_overdue_selection();
+        }
+    }
+
+    fn start_snooze(&mut self, task_idx: usize) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::Snooze(task_idx),
+            buffer: String::new(),
+        });
+    }
+
+    fn start_go_to_week(&mut self) {
+        self.prompt = Some(Prompt {
+            kind: PromptKind::GoToWeek,
+            buffer: String::new(),
+        });
+    }
+
+    /// Start narrowing the list in place, picking up any filter already set.
+    fn start_filter(&mut self) {
+        let buffer = self.list_filter.clone().unwrap_or_default();
+        self.list_filter = Some(buffer.clone());
+        self.prompt = Some(Prompt {
+            kind: PromptKind::Filter,
+            buffer,
+        });
+    }
+
+    fn clear_filter(&mut self) {
+        self.list_filter = None;
+        self.selected_task = 0;
+    }
+
+    fn log_time_selected(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) {
+            self.prompt = Some(Prompt {
+                kind: PromptKind::LogTime(idx),
+                buffer: String::new(),
+            });
+        }
+    }
+
+    fn snooze_selected(&mut self) {
+        let tasks = self.current_tasks();
+        if let Some(&(idx, _)) = tasks.get(self.selected_task).filter(|(_, t)| !t.is_heading()) {
+            self.start_snooze(idx);
+        }
+    }
+
+    fn snooze_overdue(&mut self) {
+        let overdue = self.data.overdue_tasks(&self.config.today(), &self.config);
+        if let Some(&(idx, _)) = overdue.get(self.overdue_selected) {
+            self.start_snooze(idx);
+        }
+    }
+
+    fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+
+        match prompt.kind {
+            PromptKind::Snooze(idx) => {
+                let today = self.config.today();
+                let until = match parse_snooze(&prompt.buffer) {
+                    Some(days) => Some(today + chrono::Duration::days(days)),
+                    None => parse_natural_date(&prompt.buffer, today),
+                };
+                let Some(until) = until else {
+                    self.status_message = Some(format!("Invalid snooze '{}' - use e.g. 1d, 1w or fri", prompt.buffer));
+                    self.prompt = Some(prompt);
+                    return;
+                };
+                self.data.tasks[idx].move_to(Some(until));
+                self.status_message = Some(format!("Snoozed until {}", until.format("%a, %b %d")));
+                self.persist();
+                self.clamp_overdue_selection();
+            }
+            PromptKind::ConfirmQuit => {
+                if prompt.buffer.trim().eq_ignore_ascii_case("y") {
+                    self.should_quit = true;
+                }
+            }
+            PromptKind::GoToWeek => {
+                let Some(monday) = parse_iso_week(&prompt.buffer, self.current_date) else {
+                    self.status_message = Some(format!("No such week '{}' - use e.g. 23 or 2026-W05", prompt.buffer));
+                    self.prompt = Some(prompt);
+                    return;
+                };
+                self.current_date = monday;
+                self.selected_task = 0;
+                let week = monday.iso_week();
+                self.status_message = Some(format!("Week {} of {}", week.week(), week.year()));
+            }
+            PromptKind::Filter => {
+                if prompt.buffer.is_empty() {
+                    self.clear_filter();
+                } else {
+                    self.status_message = Some(format!("Showing tasks matching '{}' (Esc to clear)", prompt.buffer));
+                }
+            }
+            PromptKind::LogTime(idx) => {
+                let Some(minutes) = parse_logged_time(&prompt.buffer) else {
+                    self.status_message = Some(format!("Invalid time '{}' - use e.g. 25, 1h or 1h30m", prompt.buffer));
+                    self.prompt = Some(prompt);
+                    return;
+                };
+                let task = &mut self.data.tasks[idx];
+                task.logged_minutes = (i64::from(task.logged_minutes) + minutes).clamp(0, u32::MAX.into()) as u32;
+                self.status_message = Some(format!(
+                    "Logged {} on '{}'",
+                    format_duration(chrono::Duration::minutes(task.logged_minutes.into())),
+                    task.title()
+                ));
+                self.persist();
+            }
+        }
+    }
+
+    /// Keep the sidebar selection in range after an item leaves the overdue
+    /// list, handing focus back to the task list once it's empty.
+    fn clamp_overdue_selection(&mut self) {
+        let count = self.data.overdue_tasks(&self.config.today(), &self.config).len();
+        if count == 0 {
+            self.sidebar_focused = false;
+            self.overdue_selected = 0;
+        } else {
+            self.overdue_selected = self.overdue_selected.min(count - 1);
+        }
+        self.clamp_selection();
+    }
+
+    /// Tasks affected by a bulk operation, without changing anything yet.
+    fn bulk_targets(&self, op: BulkOp) -> Vec<usize> {
+        match op {
+            BulkOp::RollOverOverdue => self
+                .data
+                .overdue_tasks(&self.config.today(), &self.config)
+                .into_iter()
+                .map(|(idx, _)| idx)
+                .collect(),
+            BulkOp::ClearCompleted => self
+                .listed_tasks()
+                .into_iter()
+                .filter(|(_, t)| t.is_done() && !t.is_heading())
+                .map(|(idx, _)| idx)
+                .collect(),
+            BulkOp::ScheduleInbox => self.inbox_plan().into_iter().map(|(idx, _)| idx).collect(),
+            // Counted deletes build their preview directly in `delete_tasks`
+            BulkOp::DeleteTasks => Vec::new(),
+            BulkOp::CompleteAll | BulkOp::ReopenAll => self
+                .listed_tasks()
+                .into_iter()
+                .filter(|(_, t)| !t.is_heading() && t.is_done() == (op == BulkOp::ReopenAll))
+                .map(|(idx, _)| idx)
+                .collect(),
+        }
+    }
+
+    /// Mark everything in the list done, or if it already all is, not done.
+    fn toggle_all_tasks(&mut self) {
+        let op = if self.bulk_targets(BulkOp::CompleteAll).is_empty() {
+            BulkOp::ReopenAll
+        } else {
+            BulkOp::CompleteAll
+        };
+        self.preview_bulk(op);
+    }
+
+    /// Delete `count` tasks starting at the selection, asking for confirmation
+    /// when that's more than `confirm_delete_above`.
+    fn delete_tasks(&mut self, count: usize) {
+        if count <= 1 && self.config.confirm_delete_above >= 1 {
+            self.delete_task();
+            return;
+        }
+
+        let targets: Vec<usize> = self
+            .current_tasks()
+            .into_iter()
+            .skip(self.selected_task)
+            .take(count)
+            .map(|(idx, _)| idx)
+            .collect();
+        if !targets.is_empty() {
+            self.confirm_bulk(BulkPreview {
+                op: BulkOp::DeleteTasks,
+                targets,
+                planned: Vec::new(),
+            });
+        }
+    }
+
+    /// Show a bulk change for confirmation, or apply it straight away when
+    /// it only deletes a few tasks.
+    fn confirm_bulk(&mut self, preview: BulkPreview) {
+        let deletes = matches!(preview.op, BulkOp::ClearCompleted | BulkOp::DeleteTasks);
+        let small = preview.targets.len() <= self.config.confirm_delete_above;
+        self.bulk_preview = Some(preview);
+        if deletes && small {
+            self.apply_bulk();
+        }
+    }
+
+    fn inbox_plan(&self) -> Vec<(usize, NaiveDate)> {
+        self.data
+            .plan_inbox(self.config.today(), self.config.daily_capacity)
+    }
+
+    fn preview_bulk(&mut self, op: BulkOp) {
+        let targets = self.bulk_targets(op);
+        if targets.is_empty() {
+            self.status_message = Some(match op {
+                BulkOp::RollOverOverdue => "No overdue tasks to roll over".to_string(),
+                BulkOp::ClearCompleted => "No completed tasks to clear".to_string(),
+                BulkOp::ScheduleInbox if self.config.daily_capacity == 0 => {
+                    "Set daily_capacity above 0 to plan the inbox".to_string()
+                }
+                BulkOp::ScheduleInbox => "No undated tasks to schedule".to_string(),
+                BulkOp::DeleteTasks => "No tasks to delete".to_string(),
+                BulkOp::CompleteAll | BulkOp::ReopenAll => "No tasks here".to_string(),
+            });
+        } else {
+            let planned = if op == BulkOp::ScheduleInbox {
+                self.inbox_plan().into_iter().map(|(_, date)| date).collect()
+            } else {
+                Vec::new()
+            };
+            self.confirm_bulk(BulkPreview { op, targets, planned });
+        }
+    }
+
+    fn apply_bulk(&mut self) {
+        let Some(preview) = self.bulk_preview.take() else {
+            return;
+        };
+
+        let count = preview.targets.len();
+        match preview.op {
+            BulkOp::RollOverOverdue => {
+                let today = self.config.today();
+                for &idx in &preview.targets {
+                    self.data.tasks[idx].move_to(Some(today));
+                }
+                self.status_message = Some(format!("Moved {} task(s) to today", count));
+            }
+            BulkOp::ClearCompleted | BulkOp::DeleteTasks => {
+                self.remove_tasks(preview.targets);
+                let what = if preview.op == BulkOp::ClearCompleted { "completed task(s)" } else { "task(s)" };
+                self.status_message = Some(if self.config.trash_days.is_some() {
+                    format!("Moved {} {} to the trash (X to restore)", count, what)
+                } else {
+                    format!("Deleted {} {}", count, what)
+                });
+            }
+            BulkOp::ScheduleInbox => {
+                for (&idx, &date) in preview.targets.iter().zip(&preview.planned) {
+                    self.data.tasks[idx].move_to(Some(date));
+                }
+                self.status_message = Some(format!("Scheduled {} inbox task(s)", count));
+            }
+            BulkOp::CompleteAll | BulkOp::ReopenAll => {
+                let status = if preview.op == BulkOp::CompleteAll { Status::Done } else { Status::Todo };
+                for &idx in &preview.targets {
+                    self.data.tasks[idx].set_status(status);
+                    self.note_completion(idx);
+                }
+                self.status_message = Some(if status == Status::Done {
+                    format!("Marked {} task(s) done", count)
+                } else {
+                    format!("Marked {} task(s) not done", count)
+                });
+            }
+        }
+        self.persist();
+        self.clamp_selection();
+        self.clamp_overdue_selection();
+    }
+
+    /// Open the selected task's link, or offer a choice when it has several.
+    fn open_task_link(&mut self) {
+        let tasks = self.current_tasks();
+        let Some(&(_, task)) = tasks.get(self.selected_task) else {
+            return;
+        };
+
+        let mut urls = extract_urls(&task.content);
+        urls.extend(task.location.as_deref().map(map_url));
+        match urls.len() {
+            0 => self.status_message = Some("No link or location in this task".to_string()),
+            1 => self.open_url(&urls.remove(0)),
+            _ => self.link_picker = Some(urls),
+        }
+    }
+
+    fn show_free_slots(&mut self) {
+        let tasks = self.data.tasks_for_date(&self.current_date);
+        let tasks: Vec<&Task> = tasks.iter().map(|&(_, t)| t).collect();
+        let config = &self.config;
+        self.free_slots = Some(free_slots(&tasks, config.work_start, config.work_end));
     }
 
-    fn prev_day(&mut self) {
-        self.current_date = self.current_date.pred_opt().unwrap_or(self.current_date);
-        self.selected_task = 0;
+    /// Copy the day being shown to the clipboard as a plain-text schedule.
+    fn copy_day(&mut self) {
+        let tasks = self.current_tasks();
+        let tasks: Vec<&Task> = tasks.iter().map(|&(_, t)| t).collect();
+        if tasks.is_empty() {
+            self.status_message = Some("Nothing scheduled to copy".to_string());
+            return;
+        }
+        let text = format_day_schedule(self.current_date, &tasks, &self.config);
+        self.status_message = Some(match copy_to_clipboard(&text) {
+            Ok(()) => format!("Copied {} line(s) to the clipboard", text.lines().count()),
+            Err(err) => format!("Couldn't copy: {}", err),
+        });
     }
 
-    fn current_tasks(&self) -> Vec<(usize, &Task)> {
-        let mut tasks = match self.view_mode {
-            ViewMode::Scheduled => self.data.tasks_for_date(&self.current_date),
-            ViewMode::Notes => Vec::new(), // No tasks in notes view
+    fn open_url(&mut self, url: &str) {
+        self.status_message = Some(match open::that_detached(url) {
+            Ok(()) => format!("Opened {}", url),
+            Err(err) => format!("Couldn't open {}: {}", url, err),
+        });
+    }
+
+    fn start_search(&mut self) {
+        self.search = Some(Search {
+            query: String::new(),
+            include_notes: self.config.search_notes,
+            hits: Vec::new(),
+            selected: 0,
+        });
+    }
+
+    /// Recompute search hits for the current query: matching tasks ordered by
+    /// date and time, followed by matching lines in the notes.
+    fn update_search(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
         };
 
-        // Sort by start time: tasks with start_time first (sorted), then tasks without
-        tasks.sort_by(|a, b| {
-            match (a.1.start_time, b.1.start_time) {
-                (Some(time_a), Some(time_b)) => time_a.cmp(&time_b),
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
+        let query = search.query.trim().to_ascii_lowercase();
+        search.hits.clear();
+        search.selected = 0;
+        if query.is_empty() {
+            return;
+        }
+
+        let mut task_hits: Vec<(usize, &Task)> = self
+            .data
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.content.to_ascii_lowercase().contains(&query))
+            .collect();
+        task_hits.sort_by_key(|(_, t)| (t.date.is_none(), t.date, t.start_time.is_none(), t.start_time));
+        search.hits.extend(task_hits.into_iter().map(|(idx, _)| SearchHit::Task(idx)));
+
+        if search.include_notes {
+            // ASCII lowercasing keeps byte offsets lined up with the original text
+            let notes = self.notes_buffer.to_ascii_lowercase();
+            let mut line_start = 0;
+            for line in notes.split('\n') {
+                if let Some(pos) = line.find(&query) {
+                    search.hits.push(SearchHit::Note(line_start + pos));
+                }
+                line_start += line.len() + 1;
             }
-        });
+        }
+    }
 
-        tasks
+    /// Move to the next (or previous) match of the last search, wrapping
+    /// around at either end.
+    fn step_search(&mut self, forward: bool) {
+        let Some(search) = self.last_search.as_mut() else {
+            return;
+        };
+        let len = search.hits.len();
+        search.selected = if forward {
+            (search.selected + 1) % len
+        } else {
+            (search.selected + len - 1) % len
+        };
+
+        let hit = search.hits[search.selected];
+        let message = format!("Match {}/{} for '{}'  (Esc to clear)", search.selected + 1, len, search.query);
+        self.jump_to_hit(hit);
+        self.status_message = Some(message);
     }
 
-    fn next_task(&mut self) {
-        let tasks = self.current_tasks();
-        if !tasks.is_empty() {
-            self.selected_task = (self.selected_task + 1) % tasks.len();
+    fn jump_to_hit(&mut self, hit: SearchHit) {
+        self.sidebar_focused = false;
+        match hit {
+            SearchHit::Task(idx) if self.today_only && self.data.tasks[idx].date != Some(self.current_date) => {
+                self.status_message = Some(match self.data.tasks[idx].date {
+                    Some(date) => format!("That task is on {} - only today is shown", date.format("%a, %b %d")),
+                    None => "That task is undated - only today is shown".to_string(),
+                });
+            }
+            SearchHit::Task(idx) => {
+                match self.data.tasks[idx].date {
+                    Some(date) => {
+                        self.view_mode = ViewMode::Scheduled;
+                        self.current_date = date;
+                    }
+                    None => self.view_mode = ViewMode::All,
+                }
+                self.selected_task = 0;
+                self.select_task_index(idx);
+            }
+            SearchHit::Note(offset) => {
+                self.view_mode = ViewMode::Notes;
+                // The notes may have changed since the search ran
+                let mut offset = offset.min(self.notes_buffer.len());
+                while !self.notes_buffer.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                self.notes_cursor = offset;
+            }
         }
     }
 
-    fn prev_task(&mut self) {
-        let tasks = self.current_tasks();
-        if !tasks.is_empty() {
-            self.selected_task = if self.selected_task == 0 {
-                tasks.len() - 1
-            } else {
-                self.selected_task - 1
-            };
+    fn toggle_view(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::Scheduled if self.today_only => ViewMode::Notes,
+            ViewMode::Scheduled => ViewMode::All,
+            ViewMode::All => ViewMode::Week,
+            ViewMode::Week => ViewMode::Notes,
+            ViewMode::Notes => ViewMode::Scheduled,
+        };
+        self.selected_task = 0;
+    }
+
+    /// Write the data file, remembering whether it worked so a failed save
+    /// isn't lost silently.
+    fn persist(&mut self) {
+        if self.read_only {
+            return;
+        }
+        match self.data.save(self.config.storage_format).and_then(|()| self.config.save()) {
+            Ok(()) => self.last_save_error = None,
+            Err(err) => {
+                self.status_message = Some(format!("Save failed: {}", err));
+                self.last_save_error = Some(err.to_string());
+            }
         }
     }
 
-    fn toggle_task(&mut self) {
-        let tasks = self.current_tasks();
-        if let Some(&(idx, _)) = tasks.get(self.selected_task) {
-            self.data.tasks[idx].completed = !self.data.tasks[idx].completed;
-            let _ = self.data.save();
+    /// Save on demand, e.g. to retry after a failed auto-save.
+    fn save_now(&mut self) {
+        self.persist();
+        if self.last_save_error.is_none() {
+            self.status_message = Some("Saved".to_string());
         }
     }
 
-    fn start_edit_task(&mut self) {
-        let tasks = self.current_tasks();
-        if let Some(&(idx, _)) = tasks.get(self.selected_task) {
-            // Clone the task data before dropping the borrow
-            let task = self.data.tasks[idx].clone();
+    /// Changes that aren't on disk yet: a failed auto-save or unsaved notes.
+    fn has_unsaved_changes(&self) -> bool {
+        self.last_save_error.is_some() || self.notes_buffer != self.data.notes
+    }
 
-            self.input_buffer = task.content;
-            self.start_time_buffer = task
-                .start_time
-                .map(|t| t.format("%H:%M").to_string())
-                .unwrap_or_default();
-            self.end_time_buffer = task
-                .end_time
-                .map(|t| t.format("%H:%M").to_string())
-                .unwrap_or_default();
-            self.editing_task_idx = Some(idx);
-            self.input_mode = true;
-            self.time_input_field = 0;
-        }
+    fn deny_read_only(&mut self) {
+        self.status_message = Some(format!(
+            "{}Read-only mode - changes are disabled",
+            self.config.glyph("🔒 ", "")
+        ));
     }
 
-    fn add_task(&mut self) {
-        if !self.input_buffer.trim().is_empty() {
-            let start_time = if !self.start_time_buffer.trim().is_empty() {
-                NaiveTime::parse_from_str(self.start_time_buffer.trim(), "%H:%M").ok()
-            } else {
-                None
-            };
+    /// Quit, unless the last save failed - then ask first.
+    fn request_quit(&mut self) {
+        if self.last_save_error.is_some() {
+            self.prompt = Some(Prompt {
+                kind: PromptKind::ConfirmQuit,
+                buffer: String::new(),
+            });
+        } else {
+            self.should_quit = true;
+        }
+    }
 
-            let end_time = if !self.end_time_buffer.trim().is_empty() {
-                NaiveTime::parse_from_str(self.end_time_buffer.trim(), "%H:%M").ok()
-            } else {
-                None
-            };
+    /// Save notes that have changed once typing has paused for
+    /// `notes_autosave_ms`.
+    fn autosave_notes(&mut self) {
+        let delay = self.config.notes_autosave_ms;
+        let Some(edited_at) = self.notes_edited_at else {
+            return;
+        };
+        if delay == 0 || edited_at.elapsed() < Duration::from_millis(delay) {
+            return;
+        }
+        self.notes_edited_at = None;
+        if self.notes_buffer != self.data.notes {
+            self.save_notes();
+        }
+    }
 
-            if let Some(idx) = self.editing_task_idx {
-                // Editing existing task
-                self.data.tasks[idx].content = self.input_buffer.trim().to_string();
-                self.data.tasks[idx].start_time = start_time;
-                self.data.tasks[idx].end_time = end_time;
-            } else {
-                // Adding new task - only in Scheduled view
-                let date = Some(self.current_date);
+    /// Characters that can still be added to the notes before `notes_max_chars`.
+    fn notes_room(&self) -> usize {
+        self.config
+            .notes_max_chars
+            .map_or(usize::MAX, |max| max.saturating_sub(self.notes_buffer.chars().count()))
+    }
 
-                self.data.tasks.push(Task {
-                    content: self.input_buffer.trim().to_string(),
-                    completed: false,
-                    date,
-                    start_time,
-                    end_time,
-                });
-            }
-            let _ = self.data.save();
-            self.input_buffer.clear();
-            self.start_time_buffer.clear();
-            self.end_time_buffer.clear();
+    /// Whether `count` more characters fit in the notes, saying so when they don't.
+    fn notes_fit(&mut self, count: usize) -> bool {
+        let fits = self.notes_room() >= count;
+        if !fits {
+            self.status_message = Some("Notes are at their notes_max_chars limit".to_string());
         }
-        self.input_mode = false;
-        self.time_input_field = 0;
-        self.editing_task_idx = None;
+        fits
     }
 
-    fn delete_task(&mut self) {
-        let tasks = self.current_tasks();
-        if let Some(&(idx, _)) = tasks.get(self.selected_task) {
-            self.data.tasks.remove(idx);
-            let _ = self.data.save();
-            if self.selected_task > 0 {
-                self.selected_task -= 1;
-            }
+    /// Start a new line in the notes with the same indent and bullet as the
+    /// current one. Enter on an empty bullet ends the list instead.
+    fn notes_newline(&mut self) {
+        let line_start = self.notes_buffer[..self.notes_cursor].rfind('\n').map_or(0, |p| p + 1);
+        let line = &self.notes_buffer[line_start..self.notes_cursor];
+        let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let bullet_len = ["- ", "* ", "+ "]
+            .iter()
+            .find(|bullet| line[indent_len..].starts_with(*bullet))
+            .map_or(0, |bullet| bullet.len());
+
+        if bullet_len > 0 && line.len() == indent_len + bullet_len {
+            self.notes_buffer.replace_range(line_start..self.notes_cursor, "");
+            self.notes_cursor = line_start;
+            return;
+        }
+
+        let prefix = format!("\n{}", &line[..indent_len + bullet_len]);
+        if !self.notes_fit(prefix.chars().count()) {
+            return;
         }
+        self.notes_buffer.insert_str(self.notes_cursor, &prefix);
+        self.notes_cursor += prefix.len();
     }
 
-    fn toggle_view(&mut self) {
-        self.view_mode = match self.view_mode {
-            ViewMode::Scheduled => ViewMode::Notes,
-            ViewMode::Notes => ViewMode::Scheduled,
+    /// Type a character into the notes, closing brackets as they're opened
+    /// and stepping over a closing bracket that's already there.
+    fn notes_insert_char(&mut self, c: char) {
+        let closing = match c {
+            '(' => Some(')'),
+            '[' => Some(']'),
+            '{' => Some('}'),
+            _ => None,
         };
-        self.selected_task = 0;
+        let next = self.notes_buffer[self.notes_cursor..].chars().next();
+
+        if matches!(c, ')' | ']' | '}') && next == Some(c) {
+            self.notes_cursor += c.len_utf8();
+            return;
+        }
+        if !self.notes_fit(1) {
+            return;
+        }
+        self.notes_buffer.insert(self.notes_cursor, c);
+        // Only close the bracket when there's room for both
+        if let Some(closing) = closing.filter(|_| self.notes_fit(1)) {
+            self.notes_buffer.insert(self.notes_cursor + c.len_utf8(), closing);
+        }
+        self.notes_cursor += c.len_utf8();
     }
 
     fn save_notes(&mut self) {
         self.data.notes = self.notes_buffer.clone();
-        let _ = self.data.save();
+        self.persist();
     }
 }
 
 fn main() -> io::Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let read_only = take_flag(&mut args, "--read-only");
+    let today_only = take_flag(&mut args, "--today");
+    let no_sample = take_flag(&mut args, "--no-sample");
+    if take_flag(&mut args, "--version") {
+        println!("keep {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    match args.first().map(String::as_str) {
+        Some("next") => return print_next_task(),
+        Some("stats") => return print_stats(&args[1..]),
+        Some("list") => return print_list(&args[1..]),
+        Some("doctor") => return run_doctor(args[1..].iter().any(|arg| arg == "--fix")),
+        Some(command @ ("export-csv" | "import-csv")) => {
+            let Some(path) = args.get(1) else {
+                eprintln!("Usage: keep {} <file.csv>", command);
+                std::process::exit(2);
+            };
+            return if command == "export-csv" { export_csv(path) } else { import_csv(path) };
+        }
+        Some("merge") => {
+            let Some(other_path) = args.get(1) else {
+                eprintln!("Usage: keep merge <other.json>");
+                std::process::exit(2);
+            };
+            return merge_file(other_path);
+        }
+        Some(other) => {
+            eprintln!("Unknown command '{}'\nUsage: keep [--read-only] [--today] [--no-sample] [--version] [next | list [--from DATE] [--to DATE] | stats [--json] [--days N] | doctor [--fix] | merge <other.json> | export-csv <file> | import-csv <file>]", other);
+            std::process::exit(2);
+        }
+        None => {}
+    }
+
     let mut terminal = setup_terminal()?;
-    let mut app = App::new()?;
+    let mut app = App::new(!no_sample)?;
+    app.read_only = read_only;
+    app.today_only = today_only;
+    if app.config.mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
 
     let result = run_app(&mut terminal, &mut app);
 
@@ -283,21 +3429,353 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
+/// Remove a `--flag` from the arguments, returning whether it was given.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+/// `keep next`: print the next upcoming task on one line, for status bars.
+fn print_next_task() -> io::Result<()> {
+    let config = Config::load();
+    let data = AppData::load(config.storage_format)?;
+    let time_format = config.time_format;
+    let now = Local::now().naive_local();
+    let today = effective_date(now, config.day_boundary_hour);
+    match data.next_upcoming(now) {
+        Some(task) => {
+            let date = task.date.unwrap_or(today);
+            let time = task.start_time.map(|t| format_time(t, time_format)).unwrap_or_default();
+            if date == today {
+                println!("{} {}", time, task.title());
+            } else {
+                println!("{} {} {}", date.format("%a %b %d"), time, task.title());
+            }
+        }
+        None => println!("nothing scheduled"),
+    }
+    Ok(())
+}
+
+/// `keep list [--from DATE] [--to DATE]`: every task dated within the
+/// inclusive range (today by default), grouped by date.
+fn print_list(args: &[String]) -> io::Result<()> {
+    let config = Config::load();
+    let today = config.today();
+    let mut from = today;
+    let mut to = today;
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let bound = match flag.as_str() {
+            "--from" => &mut from,
+            "--to" => &mut to,
+            _ => {
+                eprintln!("Usage: keep list [--from DATE] [--to DATE]");
+                std::process::exit(2);
+            }
+        };
+        match rest.next().map(|value| parse_date_input(value, today)) {
+            Some(Ok(Some(date))) => *bound = date,
+            Some(Err(err)) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+            _ => {
+                eprintln!("{} needs a date, e.g. {} 2025-03-14", flag, flag);
+                std::process::exit(2);
+            }
+        }
+    }
+    if from > to {
+        eprintln!("--from {} is after --to {}", from, to);
+        std::process::exit(2);
+    }
+
+    let data = AppData::load(config.storage_format)?;
+    let mut tasks: Vec<&Task> = data.tasks.iter().filter(|t| t.date.is_some_and(|d| (from..=to).contains(&d))).collect();
+    tasks.sort_by(|a, b| compare_tasks(a, b, config.auto_sort));
+    if tasks.is_empty() {
+        println!("Nothing scheduled from {} to {}", from, to);
+        return Ok(());
+    }
+    let days: Vec<String> = tasks
+        .chunk_by(|a, b| a.date == b.date)
+        .map(|day| format_day_schedule(day[0].date.unwrap_or(today), day, &config))
+        .collect();
+    println!("{}", days.join("\n\n"));
+    Ok(())
+}
+
+/// The longest span `keep stats --days` reports on, about ten years.
+const MAX_STATS_DAYS: usize = 3650;
+
+/// `keep stats [--json] [--days N]`: completion counts for the last N days
+/// (7 by default), as text or as JSON for other tools.
+fn print_stats(args: &[String]) -> io::Result<()> {
+    let mut args = args.to_vec();
+    let json = take_flag(&mut args, "--json");
+    let days = match args.as_slice() {
+        [] => 7,
+        [flag, n] if flag == "--days" => match n.parse::<usize>() {
+            Ok(n) if (1..=MAX_STATS_DAYS).contains(&n) => n,
+            _ => {
+                eprintln!("--days needs a number of days from 1 to {}, e.g. --days 30", MAX_STATS_DAYS);
+                std::process::exit(2);
+            }
+        },
+        _ => {
+            eprintln!("Usage: keep stats [--json] [--days N]");
+            std::process::exit(2);
+        }
+    };
+
+    let config = Config::load();
+    let data = AppData::load(config.storage_format)?;
+    let report = data.stats(config.today(), days);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    for day in &report.days {
+        println!("{}  {}/{} done, {} completed", day.date.format("%a %b %d"), day.done, day.total, day.completed);
+    }
+    match report.completion_rate {
+        Some(rate) => println!("{}/{} scheduled tasks done ({:.0}%)", report.done, report.total, rate * 100.0),
+        None => println!("Nothing scheduled in the last {} day(s)", days),
+    }
+    Ok(())
+}
+
+/// `keep doctor [--fix]`: report problems in the data file, and with `--fix`
+/// repair them.
+fn run_doctor(fix: bool) -> io::Result<()> {
+    let path = AppData::path(Config::load().storage_format);
+    let size = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => {
+            println!("No data file at {} yet", path);
+            return Ok(());
+        }
+    };
+    let mut data = match AppData::load_from(&path) {
+        Ok(data) => data,
+        Err(err) => {
+            // The app would start empty and overwrite this file on the next save
+            eprintln!("{} ({} bytes) can't be read: {}", path, size, err);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Data file: {} ({} bytes)", path, size);
+    println!(
+        "Tasks: {} ({} done), templates: {}",
+        data.tasks.len(),
+        data.tasks.iter().filter(|t| t.is_done()).count(),
+        data.templates.len()
+    );
+
+    let diagnosis = data.diagnose();
+    let describe = |idx: usize| {
+        let task = &data.tasks[idx];
+        let date = task.date.map(|d| d.to_string()).unwrap_or_else(|| "no date".to_string());
+        let times = match (task.start_time, task.end_time) {
+            (Some(start), Some(end)) => format!(" {}-{}", start.format("%H:%M"), end.format("%H:%M")),
+            _ => String::new(),
+        };
+        format!("  - #{} \"{}\" ({}{})", idx + 1, task.title().trim(), date, times)
+    };
+    for (indices, what) in [
+        (&diagnosis.empty, "with empty content"),
+        (&diagnosis.reversed, "ending before they start"),
+        (&diagnosis.duplicates, "duplicating an earlier task"),
+    ] {
+        if !indices.is_empty() {
+            println!("{} task(s) {}:", indices.len(), what);
+            for &idx in indices {
+                println!("{}", describe(idx));
+            }
+        }
+    }
+
+    if diagnosis.is_healthy() {
+        println!("No problems found");
+    } else if fix {
+        data.repair();
+        Config::migrate()?;
+        data.save_to(&path)?;
+        println!(
+            "Fixed: removed {} empty and {} duplicate task(s), cleared {} end time(s)",
+            diagnosis.empty.len(),
+            diagnosis.duplicates.len(),
+            // Removed duplicates don't count
+            diagnosis.reversed.iter().filter(|idx| !diagnosis.duplicates.contains(idx)).count()
+        );
+    } else {
+        println!("Run `keep doctor --fix` to remove empty and duplicate tasks and clear impossible end times");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `keep merge <other.json>`: fold another data file into the main one.
+fn merge_file(other_path: &str) -> io::Result<()> {
+    let other = match AppData::load_from(other_path) {
+        Ok(other) => other,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", other_path, err);
+            std::process::exit(1);
+        }
+    };
+    let format = Config::load().storage_format;
+    let mut data = AppData::load_for_update(format);
+    let (added, skipped) = data.merge(other);
+    Config::migrate()?;
+    data.save(format)?;
+    println!(
+        "Merged {} task(s) from {} into {} ({} duplicate(s) skipped)",
+        added,
+        other_path,
+        AppData::path_for(format),
+        skipped
+    );
+    Ok(())
+}
+
+/// `keep export-csv <file>`: write every task to a CSV (or `.tsv`) file.
+fn export_csv(path: &str) -> io::Result<()> {
+    let data = AppData::load(Config::load().storage_format)?;
+    std::fs::write(path, data.to_csv(csv_delimiter(path))?)?;
+    println!("Exported {} task(s) to {}", data.tasks.iter().filter(|t| !t.is_heading()).count(), path);
+    Ok(())
+}
+
+/// `keep import-csv <file>`: add the tasks from a CSV (or `.tsv`) file,
+/// skipping ones that are already there like `keep merge` does.
+fn import_csv(path: &str) -> io::Result<()> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", path, err);
+            std::process::exit(1);
+        }
+    };
+    let tasks = match AppData::tasks_from_csv(&content, csv_delimiter(path)) {
+        Ok(tasks) => tasks,
+        Err(err) => {
+            eprintln!("Could not import {}: {}", path, err);
+            std::process::exit(1);
+        }
+    };
+    let format = Config::load().storage_format;
+    let mut data = AppData::load_for_update(format);
+    let mut imported = AppData::new();
+    imported.tasks = tasks;
+    let (added, skipped) = data.merge(imported);
+    Config::migrate()?;
+    data.save(format)?;
+    println!("Imported {} task(s) from {} ({} duplicate(s) skipped)", added, path, skipped);
+    Ok(())
+}
+
 fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     Terminal::new(backend)
 }
 
 fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableBracketedPaste, DisableMouseCapture)?;
     terminal.show_cursor()?;
     Ok(())
 }
 
+/// Write the notes to a new file in the temp directory for an external
+/// editor. The name is unique and the file must not exist yet, so nothing
+/// else in the shared directory can be written through or read back.
+fn write_notes_file(notes: &str) -> io::Result<std::path::PathBuf> {
+    use io::Write;
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    for attempt in 0..100 {
+        let name = format!("keep-notes-{}-{}-{}.md", std::process::id(), nanos, attempt);
+        let path = std::env::temp_dir().join(name);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                if let Err(err) = file.write_all(notes.as_bytes()) {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(err);
+                }
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "no free temporary file name"))
+}
+
+/// Suspend the TUI and edit the notes in `$VISUAL`/`$EDITOR` (falling back
+/// to `vi`), then load the result back in and save it.
+fn edit_notes_externally<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> io::Result<()> {
+    let path = match write_notes_file(&app.notes_buffer) {
+        Ok(path) => path,
+        Err(err) => {
+            app.status_message = Some(format!("Couldn't write the notes for the editor: {}", err));
+            return Ok(());
+        }
+    };
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Allow editors with arguments, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableBracketedPaste, DisableMouseCapture)?;
+    let status = std::process::Command::new(program).args(words).arg(&path).status();
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+    if app.config.mouse {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
+    terminal.clear()?;
+
+    app.status_message = Some(match status {
+        Ok(status) if status.success() => match std::fs::read_to_string(&path) {
+            Ok(mut notes) => {
+                let max = app.config.notes_max_chars;
+                let cut = max.and_then(|max| notes.char_indices().nth(max)).map(|(end, _)| end);
+                if let Some(end) = cut {
+                    notes.truncate(end);
+                }
+                app.notes_buffer = notes;
+                app.notes_cursor = app.notes_buffer.len();
+                app.notes_edited_at = None;
+                app.save_notes();
+                match max.filter(|_| cut.is_some()) {
+                    Some(max) => format!("Notes updated from the editor, cut to notes_max_chars ({})", max),
+                    None => "Notes updated from the editor".to_string(),
+                }
+            }
+            Err(err) => format!("Couldn't read the notes back: {}; notes unchanged", err),
+        },
+        Ok(status) => format!("{} exited with {}; notes unchanged", program, status),
+        Err(err) => format!("Couldn't start {}: {}", program, err),
+    });
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
@@ -308,9 +3786,10 @@ fn run_app<B: ratatui::backend::Backend>(
                 .direction(Direction::Vertical)
                 .margin(1)
                 .constraints([
-                    Constraint::Length(3),
+                    Constraint::Length(if app.config.compact_header { 1 } else { 3 }),
                     Constraint::Min(0),
-                    Constraint::Length(3),
+                    // The add/edit form shows its controls on a second line
+                    Constraint::Length(if app.input_mode { 4 } else { 3 }),
                 ])
                 .split(f.area());
 
@@ -318,60 +3797,149 @@ fn run_app<B: ratatui::backend::Backend>(
             let content_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Min(40),
-                    Constraint::Length(35),
+                    Constraint::Min(0),
+                    Constraint::Length(if app.config.show_sidebar {
+                        sidebar_width(main_chunks[1].width, app.config.sidebar_width)
+                    } else {
+                        0
+                    }),
                 ])
                 .split(main_chunks[1]);
+            app.task_area = content_chunks[0];
 
-            // Calculate statistics
-            let tasks = app.current_tasks();
+            // Calculate statistics (headings are dividers, not tasks)
+            let tasks: Vec<(usize, &Task)> = app
+                .listed_tasks()
+                .into_iter()
+                .filter(|(_, t)| !t.is_heading())
+                .collect();
             let total = tasks.len();
-            let completed = tasks.iter().filter(|(_, t)| t.completed).count();
-            let pending = total - completed;
+            let completed = tasks.iter().filter(|(_, t)| t.is_done()).count();
+            let in_progress = tasks.iter().filter(|(_, t)| t.status == Status::InProgress).count();
+            let pending = total - completed - in_progress;
 
-            let (header_text, title, title_style) = match app.view_mode {
+            let config = &app.config;
+            let (header_text, title) = match app.view_mode {
                 ViewMode::Scheduled => {
-                    let today = Local::now().date_naive();
+                    let today = app.config.today();
                     let date_str = if app.current_date == today {
-                        format!("📅 {} (Today)", app.current_date.format("%A, %B %d, %Y"))
+                        format!("{}{} (Today)", config.glyph("📅 ", ""), config.full_date(app.current_date))
                     } else {
-                        format!("📅 {}", app.current_date.format("%A, %B %d, %Y"))
+                        format!("{}{}", config.glyph("📅 ", ""), config.full_date(app.current_date))
                     };
-                    (date_str, "Scheduled Tasks", Style::default().fg(Color::Cyan).bold())
+                    (date_str, "Scheduled Tasks")
                 }
-                ViewMode::Notes => (
-                    "📝 Free-form Notes & Ideas".to_string(),
-                    "Notes",
-                    Style::default().fg(Color::Rgb(150, 100, 200)).bold()
-                ),
+                ViewMode::All => (format!("{}Every Task, All Dates", config.glyph("🗂  ", "")), "All Tasks"),
+                ViewMode::Week => {
+                    let week = app.current_date.week(Weekday::Mon);
+                    let iso = app.current_date.iso_week();
+                    (
+                        format!(
+                            "{}Week {} of {}: {} {} {}",
+                            config.glyph("🗓  ", ""),
+                            iso.week(),
+                            iso.year(),
+                            week.first_day().format("%b %d"),
+                            config.glyph("–", "-"),
+                            week.last_day().format("%b %d, %Y")
+                        ),
+                        "Week",
+                    )
+                }
+                ViewMode::Notes => (format!("{}Free-form Notes & Ideas", config.glyph("📝 ", "")), "Notes"),
             };
+            let accent = config.accent(app.view_mode);
+            let title_style = Style::default().fg(accent).bold();
 
-            let stats = format!(" {} Total  •  {} Pending  •  {} Done ", total, pending, completed);
+            let dot = config.glyph("•", "|");
+            let stats = format!(
+                " {} Total  {dot}  {} Pending  {dot}  {} In Progress  {dot}  {} Done ",
+                total, pending, in_progress, completed
+            );
 
             let header_block = Block::default()
                 .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Cyan))
+                .border_set(app.config.border_set())
+                .border_style(Style::default().fg(accent))
                 .title(
                     Line::from(vec![
                         Span::styled("  Keep ", Style::default().fg(Color::White).bold()),
-                        Span::styled("▸", Style::default().fg(Color::Cyan)),
+                        Span::styled(config.glyph("▸", ">"), Style::default().fg(accent)),
                         Span::styled(" Task Manager  ", Style::default().fg(Color::DarkGray)),
                     ])
                 )
                 .title_alignment(Alignment::Left);
 
-            let header_content = vec![
-                Line::from(vec![
-                    Span::styled(&header_text, title_style),
-                    Span::raw("  "),
-                    Span::styled(&stats, Style::default().fg(Color::DarkGray)),
-                ]),
-            ];
+            let mut header_spans = vec![];
+            if app.has_unsaved_changes() {
+                header_spans.push(Span::styled(config.glyph("● ", "* "), Style::default().fg(Color::Yellow).bold()));
+            }
+            header_spans.extend([
+                Span::styled(&header_text, title_style),
+                Span::raw("  "),
+                Span::styled(&stats, Style::default().fg(Color::DarkGray)),
+            ]);
+            let now = Local::now().naive_local();
+            if app.view_mode == ViewMode::Scheduled && app.current_date == app.config.today() {
+                let next = app
+                    .data
+                    .next_upcoming(now)
+                    .filter(|t| t.date == Some(app.current_date))
+                    .and_then(|t| Some((t, t.start_time?)));
+                let countdown = match next {
+                    Some((task, start)) => {
+                        // Round up, so a task starting in 30 seconds isn't "in 0m"
+                        let minutes = ((app.current_date.and_time(start) - now).num_seconds() + 59) / 60;
+                        format!(
+                            " Next: {} in {} ",
+                            truncate(task.title(), 24),
+                            format_duration(chrono::Duration::minutes(minutes))
+                        )
+                    }
+                    None => format!(" Next: {} ", config.glyph("—", "-")),
+                };
+                header_spans.push(Span::styled(countdown, Style::default().fg(accent)));
+            }
+            let logged = app.data.logged_minutes_on(app.current_date);
+            if app.view_mode == ViewMode::Scheduled && logged > 0 {
+                header_spans.push(Span::styled(
+                    format!(" {}{} logged ", config.glyph("⏲ ", ""), format_duration(chrono::Duration::minutes(logged.into()))),
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            let completions = app.data.completions_per_day(app.config.today(), 14);
+            header_spans.push(Span::styled(
+                format!(" {} ", sparkline(&completions, app.config.ascii_mode)),
+                Style::default().fg(Color::Green),
+            ));
+            let filter_label = match app.time_filter {
+                TimeFilter::All => None,
+                TimeFilter::Timed => Some(config.glyph(" ⏱ Timed only ", " Timed only ")),
+                TimeFilter::Untimed => Some(config.glyph(" ○ Untimed only ", " Untimed only ")),
+            };
+            if let Some(label) = filter_label.filter(|_| app.view_mode != ViewMode::Notes) {
+                header_spans.push(Span::styled(label, Style::default().fg(Color::Black).bg(Color::Cyan)));
+            }
+            if let Some(filter) = app.list_filter.as_ref().filter(|f| !f.is_empty() && app.view_mode != ViewMode::Notes) {
+                header_spans.push(Span::styled(
+                    format!(" & {} ", filter),
+                    Style::default().fg(Color::Black).bg(Color::Yellow),
+                ));
+            }
+            // Keep overdue work visible when the sidebar isn't
+            let overdue_total = app.data.overdue_tasks(&app.config.today(), &app.config).len();
+            if content_chunks[1].width == 0 && overdue_total > 0 {
+                header_spans.push(Span::styled(
+                    format!(" {}{} Overdue ", config.glyph("⚠ ", "!"), overdue_total),
+                    Style::default().fg(Color::Red).bold(),
+                ));
+            }
+            let header_content = vec![Line::from(header_spans)];
 
-            let header = Paragraph::new(header_content)
-                .block(header_block)
-                .alignment(Alignment::Center);
+            let mut header = Paragraph::new(header_content).alignment(Alignment::Center);
+            if !config.compact_header {
+                header = header.block(header_block);
+            }
             f.render_widget(header, main_chunks[0]);
 
             // Main content area - either tasks or notes
@@ -385,29 +3953,43 @@ fn run_app<B: ratatui::backend::Backend>(
                     format!("{}█{}", before, after)
                 };
 
+                let line_count = text_with_cursor.lines().count();
+                let gutter_width = line_count.to_string().len();
                 let notes_lines: Vec<Line> = text_with_cursor
                     .lines()
-                    .map(|line| {
-                        let spans: Vec<Span> = line.chars().map(|ch| {
+                    .enumerate()
+                    .map(|(number, line)| {
+                        let mut spans: Vec<Span> = Vec::new();
+                        if app.config.notes_line_numbers {
+                            spans.push(Span::styled(
+                                format!("{:>width$} {} ", number + 1, config.glyph("│", "|"), width = gutter_width),
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                        spans.extend(line.chars().map(|ch| {
                             if ch == '█' {
                                 Span::styled(
-                                    "█",
+                                    config.glyph("█", "_"),
                                     Style::default().fg(Color::White)
                                 )
                             } else {
                                 Span::raw(ch.to_string())
                             }
-                        }).collect();
+                        }));
                         Line::from(spans)
                     })
                     .collect();
 
-                let notes_display = if app.notes_buffer.is_empty() {
-                    vec![
-                        Line::from(vec![
-                            Span::styled("█", Style::default().fg(Color::White)),
-                        ]),
-                    ]
+                let notes_display = if let Some(selected) = app.notes_outline {
+                    let visible = content_chunks[0].height.saturating_sub(2) as usize;
+                    outline_lines(app, selected, visible)
+                } else if app.notes_buffer.is_empty() {
+                    let mut spans = Vec::new();
+                    if app.config.notes_line_numbers {
+                        spans.push(Span::styled(format!("1 {} ", config.glyph("│", "|")), Style::default().fg(Color::DarkGray)));
+                    }
+                    spans.push(Span::styled(config.glyph("█", "_"), Style::default().fg(Color::White)));
+                    vec![Line::from(spans)]
                 } else {
                     notes_lines
                 };
@@ -416,8 +3998,8 @@ fn run_app<B: ratatui::backend::Backend>(
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
-                            .border_style(Style::default().fg(Color::Rgb(150, 100, 200)))
+                            .border_set(app.config.border_set())
+                            .border_style(Style::default().fg(accent))
                             .title(Line::from(vec![
                                 Span::raw("  "),
                                 Span::styled(title, title_style),
@@ -427,27 +4009,75 @@ fn run_app<B: ratatui::backend::Backend>(
                     )
                     .alignment(Alignment::Left);
                 f.render_widget(notes_widget, content_chunks[0]);
+            } else if app.view_mode == ViewMode::Week {
+                render_week(f, app, content_chunks[0]);
             } else {
                 // Tasks view
                 let tasks = app.current_tasks();
+                let today = app.config.today();
+                let one_line = app.config.one_line_rows;
+                let time_width = app.max_time_len();
 
-                let rows: Vec<Row> = tasks
+                let mut rows: Vec<Row> = tasks
                 .iter()
                 .enumerate()
                 .map(|(i, (_, task))| {
-                    let (checkbox, checkbox_style) = if task.completed {
-                        ("●", Style::default().fg(Color::Green))
-                    } else {
-                        ("○", Style::default().fg(Color::DarkGray))
+                    if task.is_heading() && one_line {
+                        let time = task
+                            .start_time
+                            .map(|t| format_time(t, app.config.time_format))
+                            .unwrap_or_default();
+                        let text = format!(
+                            "{:<width$} {rule} {} {rule}",
+                            time,
+                            task.title(),
+                            width = time_width,
+                            rule = config.glyph("━━", "==")
+                        );
+                        let mut row_style = Style::default().fg(Color::LightCyan).bold();
+                        if i == app.selected_task {
+                            row_style = row_style.bg(Color::Rgb(40, 40, 60));
+                        }
+                        return Row::new(vec![Cell::from(text)]).style(row_style).height(1);
+                    }
+                    if task.is_heading() {
+                        let heading_style = Style::default().fg(Color::LightCyan).bold();
+                        let mut cells = vec![Cell::from("")];
+                        if app.view_mode == ViewMode::All {
+                            cells.push(Cell::from(""));
+                        }
+                        cells.extend(vec![
+                            Cell::from(
+                                task.start_time
+                                    .map(|t| format!("   {}", format_time(t, app.config.time_format)))
+                                    .unwrap_or_default(),
+                            )
+                            .style(heading_style),
+                            Cell::from(""),
+                            Cell::from(format!("{rule} {} {rule}", task.title(), rule = config.glyph("━━", "=="))).style(heading_style),
+                        ]);
+                        let row_style = if i == app.selected_task {
+                            Style::default().bg(Color::Rgb(40, 40, 60))
+                        } else {
+                            Style::default()
+                        };
+                        return Row::new(cells).style(row_style).height(1);
+                    }
+
+                    let (checkbox, checkbox_style) = match task.status {
+                        Status::Done => (config.glyph("●", "[x]"), Style::default().fg(Color::Green)),
+                        Status::InProgress => (config.glyph("◐", "[~]"), Style::default().fg(Color::Yellow)),
+                        Status::Todo => (config.glyph("○", "[ ]"), Style::default().fg(Color::DarkGray)),
                     };
 
+                    let time_format = app.config.time_format;
                     let start_time_str = task
                         .start_time
-                        .map(|t| format!("🕐 {}", t.format("%H:%M")))
+                        .map(|t| format!("{}{}", config.glyph("🕐 ", ""), format_time(t, time_format)))
                         .unwrap_or_else(|| "   --:--".to_string());
                     let end_time_str = task
                         .end_time
-                        .map(|t| format!("🕐 {}", t.format("%H:%M")))
+                        .map(|t| format!("{}{}", config.glyph("🕐 ", ""), format_time(t, time_format)))
                         .unwrap_or_else(|| "   --:--".to_string());
 
                     let (row_style, content_style) = if i == app.selected_task {
@@ -455,7 +4085,13 @@ fn run_app<B: ratatui::backend::Backend>(
                             Style::default().bg(Color::Rgb(40, 40, 60)),
                             Style::default().fg(Color::White).bold()
                         )
-                    } else if task.completed {
+                    } else if task.is_done() && app.config.completed_placement == CompletedPlacement::InPlace {
+                        // Left among the unfinished tasks, so mark them more clearly
+                        (
+                            Style::default(),
+                            Style::default().fg(Color::DarkGray).crossed_out()
+                        )
+                    } else if task.is_done() {
                         (
                             Style::default(),
                             Style::default().fg(Color::DarkGray)
@@ -467,25 +4103,156 @@ fn run_app<B: ratatui::backend::Backend>(
                         )
                     };
 
-                    Row::new(vec![
-                        Cell::from(checkbox).style(checkbox_style),
+                    let blocker = app.data.blocker(task);
+                    // Waiting on another task: dimmed until that one is done
+                    let content_style = if blocker.is_some() && i != app.selected_task {
+                        Style::default().fg(Color::DarkGray)
+                    } else {
+                        content_style
+                    };
+                    let mut content_spans = Vec::new();
+                    if blocker.is_some() {
+                        content_spans.push(Span::raw(config.glyph("🔒 ", "(blocked) ")));
+                    }
+                    if task.focus {
+                        content_spans.push(Span::styled(config.glyph("⭐ ", "! "), Style::default().fg(Color::Yellow).bold()));
+                    }
+                    if task.pinned {
+                        content_spans.push(Span::raw(config.glyph("📌 ", "* ")));
+                    }
+                    content_spans.extend(highlight_match(task.title(), app.list_filter.as_deref()));
+                    // Further lines of the content go on rows of their own
+                    let more_lines: Vec<Line> = task
+                        .content
+                        .lines()
+                        .skip(1)
+                        .map(|line| Line::from(highlight_match(line, app.list_filter.as_deref())))
+                        .collect();
+                    if one_line && !more_lines.is_empty() {
+                        content_spans.push(Span::raw(config.glyph(" …", " ...")));
+                    }
+                    content_spans.extend([
+                        match &task.label {
+                            Some(label) => Span::styled(
+                                format!("  [{}]", label),
+                                Style::default().fg(app.config.label_color(label)),
+                            ),
+                            None => Span::raw(""),
+                        },
+                        match &task.location {
+                            Some(location) => Span::styled(
+                                format!("  {}{}", config.glyph("📍 ", "@ "), location),
+                                Style::default().fg(Color::LightBlue),
+                            ),
+                            None => Span::raw(""),
+                        },
+                        match task.logged_minutes {
+                            0 => Span::raw(""),
+                            minutes => Span::styled(
+                                format!("  {}{}", config.glyph("⏲ ", "+"), format_duration(chrono::Duration::minutes(minutes.into()))),
+                                Style::default().fg(Color::Magenta),
+                            ),
+                        },
+                        match task.stale_days(today) {
+                            Some(days) => Span::styled(format!("  {}d", days), Style::default().fg(Color::DarkGray)),
+                            None => Span::raw(""),
+                        },
+                    ]);
+
+                    if one_line {
+                        // "09:00 ○ Task" in a single column
+                        let mut spans = Vec::new();
+                        if app.view_mode == ViewMode::All {
+                            let date_str = task
+                                .date
+                                .map(|d| d.format("%a %b %d ").to_string())
+                                .unwrap_or_else(|| "No date    ".to_string());
+                            spans.push(Span::styled(date_str, Style::default().fg(Color::Yellow)));
+                        }
+                        let time = task.start_time.map(|t| format_time(t, time_format)).unwrap_or_default();
+                        spans.extend([
+                            Span::styled(format!("{:<width$} ", time, width = time_width), Style::default().fg(Color::Cyan)),
+                            Span::styled(checkbox, checkbox_style),
+                            Span::raw(" "),
+                        ]);
+                        spans.extend(content_spans.into_iter().map(|span| {
+                            let style = content_style.patch(span.style);
+                            span.style(style)
+                        }));
+                        return Row::new(vec![Cell::from(Line::from(spans))]).style(row_style).height(1);
+                    }
+
+                    let mut cells = vec![Cell::from(checkbox).style(checkbox_style)];
+                    if app.view_mode == ViewMode::All {
+                        let date_str = task
+                            .date
+                            .map(|d| d.format("%a %b %d").to_string())
+                            .unwrap_or_else(|| "No date".to_string());
+                        cells.push(Cell::from(date_str).style(Style::default().fg(Color::Yellow)));
+                    }
+                    cells.extend(vec![
                         Cell::from(start_time_str).style(if task.start_time.is_some() { Style::default().fg(Color::Cyan) } else { Style::default().fg(Color::DarkGray) }),
                         Cell::from(end_time_str).style(if task.end_time.is_some() { Style::default().fg(Color::Magenta) } else { Style::default().fg(Color::DarkGray) }),
-                        Cell::from(task.content.clone()).style(content_style),
-                    ])
+                        Cell::from(Text::from_iter(std::iter::once(Line::from(content_spans)).chain(more_lines)))
+                            .style(content_style),
+                    ]);
+
+                    Row::new(cells)
                     .style(row_style)
-                    .height(1)
+                    .height(task.row_height() as u16)
                 })
                 .collect();
 
-            let header = Row::new(vec![
-                Cell::from("  ").style(Style::default().fg(Color::Cyan).bold()),
-                Cell::from("Start Time").style(Style::default().fg(Color::Cyan).bold()),
-                Cell::from("End Time").style(Style::default().fg(Color::Magenta).bold()),
-                Cell::from("Task Description").style(Style::default().fg(Color::White).bold()),
-            ])
+            let completed = app.completed_count();
+            if completed > 0 {
+                let label = Cell::from(if app.show_done {
+                    format!("{} Completed ({})  z to fold", config.glyph("▾", "v"), completed)
+                } else {
+                    format!("{} Completed ({})  z to show", config.glyph("▸", ">"), completed)
+                });
+                let mut cells = Vec::new();
+                if !one_line {
+                    cells.push(Cell::from(""));
+                    if app.view_mode == ViewMode::All {
+                        cells.push(Cell::from(""));
+                    }
+                    cells.extend([Cell::from(""), Cell::from("")]);
+                }
+                cells.push(label);
+                // The section header goes above the first finished task
+                let position = tasks
+                    .iter()
+                    .position(|&(_, task)| app.in_completed_section(task))
+                    .unwrap_or(rows.len());
+                rows.insert(position, Row::new(cells).style(Style::default().fg(Color::DarkGray)));
+            }
+
+            let compact = app.config.density == Density::Compact;
+            let mut header_cells = vec![Cell::from("  ").style(Style::default().fg(accent).bold())];
+            let mut widths = vec![Constraint::Length(3)];
+            if one_line {
+                header_cells = vec![Cell::from("Tasks").style(Style::default().fg(Color::White).bold())];
+                widths = vec![Constraint::Min(30)];
+            } else {
+                if app.view_mode == ViewMode::All {
+                    header_cells.push(Cell::from("Date").style(Style::default().fg(Color::Yellow).bold()));
+                    widths.push(Constraint::Length(10));
+                }
+                header_cells.extend(vec![
+                    Cell::from("Start Time").style(Style::default().fg(accent).bold()),
+                    Cell::from("End Time").style(Style::default().fg(Color::Magenta).bold()),
+                    Cell::from("Task Description").style(Style::default().fg(Color::White).bold()),
+                ]);
+                widths.extend([
+                    Constraint::Length(12),
+                    Constraint::Length(12),
+                    Constraint::Min(30),
+                ]);
+            }
+
+            let header = Row::new(header_cells)
             .height(1)
-            .bottom_margin(1);
+            .bottom_margin(if compact { 0 } else { 1 });
 
             let title_line = Line::from(vec![
                 Span::raw("  "),
@@ -493,73 +4260,120 @@ fn run_app<B: ratatui::backend::Backend>(
                 Span::raw("  "),
             ]);
 
-            let tasks_table = Table::new(
-                rows,
-                [
-                    Constraint::Length(3),
-                    Constraint::Length(12),
-                    Constraint::Length(12),
-                    Constraint::Min(30),
-                ],
-            )
-            .header(header)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Rgb(100, 100, 120)))
-                    .title(title_line)
-                    .title_alignment(Alignment::Left)
-            )
-            .column_spacing(2);
+            let tasks_block = Block::default()
+                .borders(Borders::ALL)
+                .border_set(app.config.border_set())
+                .border_style(Style::default().fg(accent))
+                .title(title_line)
+                .title_alignment(Alignment::Left);
+
+            if rows.is_empty() {
+                // An empty day gets a message rather than a bare table header
+                let message = if app.list_filter.as_ref().is_some_and(|f| !f.is_empty()) {
+                    "No tasks match the filter - Esc clears it".to_string()
+                } else if app.time_filter != TimeFilter::All {
+                    "No tasks match the time filter - F changes it".to_string()
+                } else {
+                    let empty = if app.view_mode == ViewMode::All { "No tasks yet" } else { "No tasks for this day" };
+                    if app.read_only {
+                        empty.to_string()
+                    } else {
+                        format!("{} {} press n to add one", empty, config.glyph("—", "-"))
+                    }
+                };
+                let mut lines = vec![Line::from(""); content_chunks[0].height.saturating_sub(3) as usize / 2];
+                lines.push(Line::from(Span::styled(message, Style::default().fg(Color::DarkGray))));
+                let empty_state = Paragraph::new(lines).alignment(Alignment::Center).block(tasks_block);
+                f.render_widget(empty_state, content_chunks[0]);
+            } else {
+                let tasks_table = Table::new(rows, widths)
+                    .header(header)
+                    .block(tasks_block)
+                    .column_spacing(if compact { 1 } else { 2 });
                 f.render_widget(tasks_table, content_chunks[0]);
             }
+            }
 
             // Overdue sidebar
-            let overdue_tasks = app.data.overdue_tasks(&Local::now().date_naive());
+            let today = app.config.today();
+            let overdue_tasks = app.data.overdue_tasks(&today, &app.config);
             let overdue_count = overdue_tasks.len();
 
             let overdue_items: Vec<Line> = if overdue_tasks.is_empty() {
                 vec![
                     Line::from(""),
                     Line::from(Span::styled(
-                        "  🎉 All caught up!",
+                        format!("  {}All caught up!", config.glyph("🎉 ", "")),
                         Style::default().fg(Color::Green)
                     )),
                 ]
             } else {
-                overdue_tasks
+                // Leave the last line for a "+N more" footer when the list doesn't fit
+                let capacity = content_chunks[1].height.saturating_sub(2) as usize;
+                let shown = if overdue_count > capacity {
+                    capacity.saturating_sub(1)
+                } else {
+                    overdue_count
+                };
+                // Scroll just far enough to keep the focused item visible
+                let offset = if app.sidebar_focused && app.overdue_selected >= shown {
+                    app.overdue_selected + 1 - shown
+                } else {
+                    0
+                };
+
+                let mut lines: Vec<Line> = overdue_tasks
                     .iter()
-                    .take(10)
-                    .map(|(_, task)| {
+                    .enumerate()
+                    .skip(offset)
+                    .take(shown)
+                    .map(|(i, (_, task))| {
                         let date_str = task
                             .date
                             .map(|d| d.format("%b %d").to_string())
                             .unwrap_or_else(|| "---".to_string());
+                        let lateness_color = task
+                            .date
+                            .map(|d| overdue_color((today - d).num_days()))
+                            .unwrap_or(Color::Red);
 
-                        let task_preview = if task.content.len() > 25 {
-                            format!("{}...", &task.content[..22])
-                        } else {
-                            task.content.clone()
-                        };
+                        // What's left of the sidebar after its borders and the "⚠ Mar 14 " prefix
+                        let room = content_chunks[1].width.saturating_sub(11) as usize;
+                        let task_preview = truncate(task.title(), room);
 
-                        Line::from(vec![
-                            Span::styled("⚠ ", Style::default().fg(Color::Red)),
-                            Span::styled(date_str, Style::default().fg(Color::Red)),
+                        let line = Line::from(vec![
+                            Span::styled(config.glyph("⚠ ", "! "), Style::default().fg(lateness_color)),
+                            Span::styled(date_str, Style::default().fg(lateness_color)),
                             Span::raw(" "),
                             Span::styled(task_preview, Style::default().fg(Color::White)),
-                        ])
+                        ]);
+                        if app.sidebar_focused && i == app.overdue_selected {
+                            line.style(Style::default().bg(Color::Rgb(40, 40, 60)).bold())
+                        } else {
+                            line
+                        }
                     })
-                    .collect()
+                    .collect();
+
+                let remaining = overdue_count - offset - lines.len();
+                if remaining > 0 {
+                    lines.push(Line::from(Span::styled(
+                        format!("  +{} more", remaining),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                lines
             };
 
             let sidebar_title = if overdue_count > 0 {
-                format!("  ⚠️  Overdue ({})  ", overdue_count)
+                format!("  {}Overdue ({})  ", config.glyph("⚠️  ", "! "), overdue_count)
             } else {
-                "  ✓ Overdue  ".to_string()
+                format!("  {}Overdue  ", config.glyph("✓ ", ""))
             };
 
-            let sidebar_border_color = if overdue_count > 0 {
+            let sidebar_border_color = if app.sidebar_focused {
+                Color::Yellow
+            } else if overdue_count > 0 {
                 Color::Red
             } else {
                 Color::Green
@@ -569,7 +4383,7 @@ fn run_app<B: ratatui::backend::Backend>(
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .border_type(BorderType::Rounded)
+                        .border_set(app.config.border_set())
                         .border_style(Style::default().fg(sidebar_border_color))
                         .title(Line::from(vec![
                             Span::styled(sidebar_title, Style::default().fg(sidebar_border_color).bold()),
@@ -577,34 +4391,169 @@ fn run_app<B: ratatui::backend::Backend>(
                         .title_alignment(Alignment::Left)
                 )
                 .alignment(Alignment::Left);
-            f.render_widget(overdue_sidebar, content_chunks[1]);
+            if content_chunks[1].width > 0 {
+                f.render_widget(overdue_sidebar, content_chunks[1]);
+            }
 
-            let help_block = if app.view_mode == ViewMode::Notes && !app.input_mode {
-                let controls_line = Line::from(vec![
-                    Span::styled(" ↑↓←→ ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+            let status_line = match &app.status_message {
+                Some(message) => Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled(message.as_str(), Style::default().fg(Color::Yellow).bold()),
+                    Span::raw(" "),
+                ]),
+                None => Line::default(),
+            };
+
+            let help_block = if let Some(prompt) = &app.prompt {
+                let question = match prompt.kind {
+                    PromptKind::Snooze(_) => format!("{}Snooze for (e.g. 1d, 1w, fri): ", config.glyph("💤 ", "")),
+                    PromptKind::ConfirmQuit => format!(
+                        "{}Last save failed ({}). Quit anyway? (y/N): ",
+                        config.glyph("⚠ ", "! "),
+                        app.last_save_error.as_deref().unwrap_or("unknown error")
+                    ),
+                    PromptKind::GoToWeek => "Go to week (e.g. 23 or 2026-W05): ".to_string(),
+                    PromptKind::LogTime(_) => format!("{}Log time spent (e.g. 25, 1h30m, -10): ", config.glyph("⏲ ", "")),
+                    PromptKind::Filter => "Filter: ".to_string(),
+                };
+                let input_line = Line::from(vec![
+                    Span::styled(question, Style::default().fg(Color::Yellow).bold()),
+                    Span::styled(prompt.buffer.as_str(), Style::default().fg(Color::White)),
+                    Span::styled(config.glyph("█", "_"), Style::default().fg(Color::White)),
+                    Span::raw("   "),
+                    Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+                    Span::raw(" OK  "),
+                    Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
+                    Span::raw(" Cancel"),
+                ]);
+
+                Paragraph::new(input_line)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_set(app.config.border_set())
+                            .border_style(Style::default().fg(Color::Yellow))
+                            .title_bottom(status_line.clone())
+                    )
+                    .alignment(Alignment::Left)
+            } else if app.view_mode == ViewMode::Notes && app.notes_outline.is_some() {
+                Paragraph::new(Line::from(vec![
+                    Span::styled(config.glyph(" ↑↓ ", " Up/Down "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::raw(" Navigate  "),
+                    Span::styled(" Enter ", Style::default().bg(accent).fg(Color::Black)),
+                    Span::raw(" Fold/Unfold  "),
+                    Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::raw(" Edit  "),
+                    Span::styled(" Tab ", Style::default().bg(accent).fg(Color::Black)),
+                    Span::raw(" Tasks  "),
+                    Span::styled(" q ", Style::default().bg(Color::Red).fg(Color::White)),
+                    Span::raw(" Quit"),
+                ]))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_set(app.config.border_set())
+                        .border_style(Style::default().fg(accent))
+                        .title(Line::from(vec![
+                            Span::raw("  "),
+                            Span::styled(format!("{}Notes Outline", config.glyph("📝 ", "")), Style::default().fg(accent).bold()),
+                            Span::raw("  "),
+                        ]))
+                        .title_alignment(Alignment::Left)
+                        .title_bottom(status_line.clone())
+                )
+                .alignment(Alignment::Left)
+            } else if app.view_mode == ViewMode::Notes && !app.input_mode {
+                let mut controls = vec![
+                    Span::styled(config.glyph(" ↑↓←→ ", " Arrows "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
                     Span::raw(" Navigate  "),
                     Span::styled(" Home/End ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
                     Span::raw(" Line  "),
-                    Span::styled(" Ctrl+S ", Style::default().bg(Color::Cyan).fg(Color::Black)),
-                    Span::raw(" Save  "),
-                    Span::styled(" Tab ", Style::default().bg(Color::Magenta).fg(Color::White)),
+                ];
+                if app.read_only {
+                    controls.push(Span::styled(config.glyph(" 🔒 Read-only ", " Read-only "), Style::default().bg(Color::DarkGray).fg(Color::White).bold()));
+                    controls.push(Span::raw("  "));
+                } else {
+                    controls.push(Span::styled(" Ctrl+S ", Style::default().bg(accent).fg(Color::Black)));
+                    controls.push(Span::raw(" Save  "));
+                    controls.push(Span::styled(" Ctrl+E ", Style::default().bg(accent).fg(Color::Black)));
+                    controls.push(Span::raw(" $EDITOR  "));
+                }
+                // Warn once the notes are within 10% of their limit
+                if let Some(max) = app.config.notes_max_chars {
+                    let used = app.notes_buffer.chars().count();
+                    if used * 10 >= max * 9 {
+                        let color = if used >= max { Color::Red } else { Color::Yellow };
+                        controls.push(Span::styled(
+                            format!(" {}/{} chars ", used, max),
+                            Style::default().bg(color).fg(Color::Black).bold(),
+                        ));
+                        controls.push(Span::raw("  "));
+                    }
+                }
+                controls.extend([
+                    Span::styled(" Ctrl+N ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::raw(" Line #  "),
+                    Span::styled(" Ctrl+O ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::raw(" Outline  "),
+                    Span::styled(" Tab ", Style::default().bg(accent).fg(Color::Black)),
                     Span::raw(" Tasks  "),
                     Span::styled(" q ", Style::default().bg(Color::Red).fg(Color::White)),
                     Span::raw(" Quit"),
                 ]);
 
-                Paragraph::new(vec![controls_line])
+                Paragraph::new(Line::from(controls))
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_set(app.config.border_set())
+                            .border_style(Style::default().fg(accent))
+                            .title(Line::from(vec![
+                                Span::raw("  "),
+                                Span::styled(format!("{}Notes Editor", config.glyph("📝 ", "")), Style::default().fg(accent).bold()),
+                                Span::raw("  "),
+                            ]))
+                            .title_alignment(Alignment::Left)
+                            .title_bottom(status_line.clone())
+                    )
+                    .alignment(Alignment::Left)
+            } else if app.sidebar_focused {
+                let mut controls = vec![
+                    Span::styled(config.glyph(" ↑ ↓ ", " Up/Down "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::raw(" Navigate  "),
+                ];
+                if !app.read_only {
+                    controls.extend([
+                        Span::styled(" Space ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+                        Span::raw(" Complete  "),
+                        Span::styled(" r ", Style::default().bg(Color::Cyan).fg(Color::Black)),
+                        Span::raw(" Move to Today  "),
+                        Span::styled(" R ", Style::default().bg(Color::Cyan).fg(Color::Black)),
+                        Span::raw(" Move All  "),
+                        Span::styled(" s ", Style::default().bg(Color::Yellow).fg(Color::Black)),
+                        Span::raw(" Snooze  "),
+                    ]);
+                }
+                controls.extend([
+                    Span::styled(" O/Esc ", Style::default().bg(Color::Magenta).fg(Color::White)),
+                    Span::raw(" Back to Tasks  "),
+                    Span::styled(" q ", Style::default().bg(Color::Red).fg(Color::White)),
+                    Span::raw(" Quit"),
+                ]);
+
+                Paragraph::new(Line::from(controls))
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
-                            .border_style(Style::default().fg(Color::Rgb(150, 100, 200)))
+                            .border_set(app.config.border_set())
+                            .border_style(Style::default().fg(Color::Yellow))
                             .title(Line::from(vec![
                                 Span::raw("  "),
-                                Span::styled("📝 Notes Editor", Style::default().fg(Color::Rgb(150, 100, 200)).bold()),
+                                Span::styled(format!("{}Overdue Triage", config.glyph("⚠️  ", "")), Style::default().fg(Color::Yellow).bold()),
                                 Span::raw("  "),
                             ]))
                             .title_alignment(Alignment::Left)
+                            .title_bottom(status_line.clone())
                     )
                     .alignment(Alignment::Left)
             } else if app.input_mode {
@@ -623,23 +4572,112 @@ fn run_app<B: ratatui::backend::Backend>(
                 } else {
                     Style::default().fg(Color::DarkGray)
                 };
+                let date_style = if app.time_input_field == 3 {
+                    Style::default().fg(Color::LightBlue).bold()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let reminder_style = if app.time_input_field == 5 {
+                    Style::default().fg(Color::LightRed).bold()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let location_style = if app.time_input_field == 6 {
+                    Style::default().fg(Color::LightBlue).bold()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let blocker_style = if app.time_input_field == 7 {
+                    Style::default().fg(Color::White).bold()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let blocker_name = app
+                    .blocked_by_input
+                    .and_then(|id| app.data.tasks.iter().find(|t| t.id == id))
+                    .map(|t| truncate(t.title(), 20))
+                    .unwrap_or_else(|| "none".to_string());
+                let label_style = match (&app.label_input, app.time_input_field == 4) {
+                    (Some(label), true) => Style::default().fg(app.config.label_color(label)).bold(),
+                    (Some(label), false) => Style::default().fg(app.config.label_color(label)),
+                    (None, true) => Style::default().fg(Color::White).bold(),
+                    (None, false) => Style::default().fg(Color::DarkGray),
+                };
 
-                let mode_text = if app.editing_task_idx.is_some() { "✏️  EDIT MODE" } else { "➕ ADD MODE" };
+                let mode_text = match (app.editing_task_idx.is_some(), app.input_kind) {
+                    (true, TaskKind::Heading) => config.glyph("✏️  EDIT HEADING", "EDIT HEADING"),
+                    (true, TaskKind::Task) => config.glyph("✏️  EDIT MODE", "EDIT MODE"),
+                    (false, TaskKind::Heading) => config.glyph("➕ ADD HEADING", "ADD HEADING"),
+                    (false, TaskKind::Task) => config.glyph("➕ ADD MODE", "ADD MODE"),
+                };
                 let mode_color = if app.editing_task_idx.is_some() { Color::Yellow } else { Color::Green };
 
+                // Line breaks in the task show as a symbol so the form stays on one line
+                let newline = config.glyph("↵", "\\n");
                 let input_line = Line::from(vec![
                     Span::styled("Task: ", task_style),
-                    Span::styled(&app.input_buffer, task_style),
+                    Span::styled(app.input_buffer[..app.input_cursor].replace('\n', newline), task_style),
+                    Span::styled(if app.time_input_field == 0 { config.glyph("█", "_") } else { "" }, task_style),
+                    Span::styled(app.input_buffer[app.input_cursor..].replace('\n', newline), task_style),
+                    Span::styled(app.content_suggestion().unwrap_or(""), Style::default().fg(Color::DarkGray)),
                     Span::raw("  "),
-                    Span::styled("│", Style::default().fg(Color::DarkGray)),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
                     Span::raw("  "),
                     Span::styled("Start: ", start_time_style),
                     Span::styled(&app.start_time_buffer, start_time_style),
                     Span::raw("  "),
-                    Span::styled("│", Style::default().fg(Color::DarkGray)),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
                     Span::raw("  "),
                     Span::styled("End: ", end_time_style),
                     Span::styled(&app.end_time_buffer, end_time_style),
+                    Span::raw("  "),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled("Date: ", date_style),
+                    Span::styled(&app.date_buffer, date_style),
+                    Span::raw("  "),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled("Label: ", label_style),
+                    Span::styled(
+                        if app.time_input_field == 4 {
+                            format!(
+                                "{} {} {}",
+                                config.glyph("◀", "<"),
+                                app.label_input.as_deref().unwrap_or("none"),
+                                config.glyph("▶", ">")
+                            )
+                        } else {
+                            app.label_input.clone().unwrap_or_else(|| "none".to_string())
+                        },
+                        label_style,
+                    ),
+                    Span::raw("  "),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled("Remind: ", reminder_style),
+                    Span::styled(&app.reminder_buffer, reminder_style),
+                    Span::styled(
+                        if app.reminder_buffer.is_empty() { "default" } else { " before" },
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw("  "),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled("Where: ", location_style),
+                    Span::styled(&app.location_buffer, location_style),
+                    Span::raw("  "),
+                    Span::styled(config.glyph("│", "|"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled("After: ", blocker_style),
+                    Span::styled(
+                        if app.time_input_field == 7 {
+                            format!("{} {} {}", config.glyph("◀", "<"), blocker_name, config.glyph("▶", ">"))
+                        } else {
+                            blocker_name
+                        },
+                        blocker_style,
+                    ),
                 ]);
 
                 let controls_line = Line::from(vec![
@@ -647,6 +4685,8 @@ fn run_app<B: ratatui::backend::Backend>(
                     Span::raw(" Switch  "),
                     Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
                     Span::raw(" Save  "),
+                    Span::styled(" Shift+Enter ", Style::default().bg(Color::Rgb(60, 60, 80)).fg(Color::White)),
+                    Span::raw(" New Line  "),
                     Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
                     Span::raw(" Cancel"),
                 ]);
@@ -655,7 +4695,7 @@ fn run_app<B: ratatui::backend::Backend>(
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
+                            .border_set(app.config.border_set())
                             .border_style(Style::default().fg(mode_color))
                             .title(Line::from(vec![
                                 Span::raw("  "),
@@ -663,31 +4703,76 @@ fn run_app<B: ratatui::backend::Backend>(
                                 Span::raw("  "),
                             ]))
                             .title_alignment(Alignment::Left)
+                            .title_bottom(status_line.clone())
                     )
                     .alignment(Alignment::Left)
             } else {
-                let mut controls = vec![
-                    Span::styled(" n ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
-                    Span::raw(" New  "),
-                    Span::styled(" e ", Style::default().bg(Color::Blue).fg(Color::White)),
-                    Span::raw(" Edit  "),
-                    Span::styled(" Space ", Style::default().bg(Color::Yellow).fg(Color::Black).bold()),
-                    Span::raw(" Toggle  "),
-                    Span::styled(" d ", Style::default().bg(Color::Red).fg(Color::White)),
-                    Span::raw(" Delete  "),
-                ];
+                let mut controls = Vec::new();
+                if let Some(search) = &app.last_search {
+                    // While a search is kept, n steps through its matches instead of adding a task
+                    controls.extend([
+                        Span::styled(format!(" /{} ", truncate(&search.query, 20)), Style::default().bg(Color::Yellow).fg(Color::Black).bold()),
+                        Span::raw("  "),
+                        Span::styled(" n/N ", Style::default().bg(Color::Yellow).fg(Color::Black)),
+                        Span::raw(" Next/Prev Match  "),
+                        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                        Span::raw(" End Search  "),
+                    ]);
+                } else if !app.read_only {
+                    controls.extend([
+                        Span::styled(" n ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+                        Span::raw(" New  "),
+                    ]);
+                }
+                if app.read_only {
+                    controls.extend([
+                        Span::styled(config.glyph(" 🔒 Read-only ", " Read-only "), Style::default().bg(Color::DarkGray).fg(Color::White).bold()),
+                        Span::raw("  "),
+                    ]);
+                } else {
+                    controls.extend([
+                        Span::styled(" e ", Style::default().bg(Color::Blue).fg(Color::White)),
+                        Span::raw(" Edit  "),
+                        Span::styled(" Space ", Style::default().bg(Color::Yellow).fg(Color::Black).bold()),
+                        Span::raw(" Toggle  "),
+                        Span::styled(" i ", Style::default().bg(Color::Yellow).fg(Color::Black)),
+                        Span::raw(" Status  "),
+                        Span::styled(" dd ", Style::default().bg(Color::Red).fg(Color::White)),
+                        Span::raw(" Delete  "),
+                        Span::styled(" p ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                        Span::raw(" Pin  "),
+                    ]);
+                }
 
-                if app.view_mode == ViewMode::Scheduled {
+                if app.today_only {
                     controls.extend(vec![
-                        Span::styled(" ← → ", Style::default().bg(Color::Cyan).fg(Color::Black)),
+                        Span::styled(" Today only ", Style::default().bg(accent).fg(Color::Black).bold()),
+                        Span::raw("  "),
+                    ]);
+                } else if app.view_mode == ViewMode::Scheduled {
+                    controls.extend(vec![
+                        Span::styled(config.glyph(" ← → ", " Left/Right "), Style::default().bg(accent).fg(Color::Black)),
                         Span::raw(" Days  "),
+                        Span::styled(config.glyph(" ⇧← → ", " Shift+Left/Right "), Style::default().bg(accent).fg(Color::Black)),
+                        Span::raw(" Busy Days  "),
+                    ]);
+                } else if app.view_mode == ViewMode::Week {
+                    controls.extend(vec![
+                        Span::styled(config.glyph(" ← → ", " Left/Right "), Style::default().bg(accent).fg(Color::Black)),
+                        Span::raw(" Weeks  "),
+                        Span::styled(" t ", Style::default().bg(accent).fg(Color::Black)),
+                        Span::raw(" Time Groups  "),
+                        Span::styled(" W ", Style::default().bg(accent).fg(Color::Black)),
+                        Span::raw(" Go to Week  "),
                     ]);
                 }
 
                 controls.extend(vec![
-                    Span::styled(" Tab ", Style::default().bg(Color::Magenta).fg(Color::White)),
+                    Span::styled(" Tab ", Style::default().bg(accent).fg(Color::Black)),
                     Span::raw(" View  "),
-                    Span::styled(" ↑ ↓ ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+                    Span::styled(" O ", Style::default().bg(Color::Red).fg(Color::White)),
+                    Span::raw(" Overdue  "),
+                    Span::styled(config.glyph(" ↑ ↓ ", " Up/Down "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
                     Span::raw(" Navigate  "),
                     Span::styled(" q ", Style::default().bg(Color::Red).fg(Color::White)),
                     Span::raw(" Quit"),
@@ -697,71 +4782,920 @@ fn run_app<B: ratatui::backend::Backend>(
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
-                            .border_style(Style::default().fg(Color::Rgb(100, 100, 120)))
+                            .border_set(app.config.border_set())
+                            .border_style(Style::default().fg(accent))
                             .title(Line::from(vec![
                                 Span::raw("  "),
-                                Span::styled("⌨️  Controls", Style::default().fg(Color::White).bold()),
+                                Span::styled(format!("{}Controls", config.glyph("⌨️  ", "")), Style::default().fg(accent).bold()),
                                 Span::raw("  "),
                             ]))
                             .title_alignment(Alignment::Left)
+                            .title_bottom(status_line.clone())
                     )
                     .alignment(Alignment::Left)
             };
 
-            f.render_widget(help_block, main_chunks[2]);
-        })?;
+            f.render_widget(help_block, main_chunks[2]);
+
+            if let Some(preview) = &app.bulk_preview {
+                render_bulk_preview(f, app, preview);
+            }
+            if let Some(urls) = &app.link_picker {
+                render_link_picker(f, app, urls);
+            }
+            if let Some(slots) = &app.free_slots {
+                render_free_slots(f, app, slots);
+            }
+            if let Some(search) = &app.search {
+                render_search(f, app, search);
+            }
+            if let Some(selected) = app.template_picker {
+                render_template_picker(f, app, selected);
+            }
+            if let Some(selected) = app.date_picker {
+                render_date_picker(f, app, selected);
+            }
+            if let Some(selected) = app.trash_picker {
+                render_trash(f, app, selected);
+            }
+            if app.show_about {
+                render_about(f, app);
+            }
+            if let Some(review) = &app.review {
+                render_review(f, app, review);
+            }
+        })?;
+        // Only between frames, so the bell can't land in the middle of one
+        if std::mem::take(&mut app.ring_bell) {
+            execute!(io::stdout(), Print("\x07"))?;
+        }
+
+        if event::poll(TICK_RATE)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => handle_input(app, key)?,
+                Event::Paste(text) => handle_paste(app, &text),
+                Event::Mouse(mouse) => handle_mouse(app, mouse),
+                _ => {}
+            }
+        }
+        if app.edit_notes_externally {
+            app.edit_notes_externally = false;
+            edit_notes_externally(terminal, app)?;
+        }
+        app.autosave_notes();
+        app.check_reminders();
+        if app.today_only {
+            // Roll over at midnight
+            app.current_date = app.config.today();
+        }
+        // Finished tasks may have just collapsed out from under the selection
+        app.clamp_selection();
+
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Width of the overdue sidebar for the given content width. The task table
+/// gets priority: the sidebar shrinks below its `preferred` width on medium
+/// terminals and disappears on narrow ones.
+fn sidebar_width(total: u16, preferred: u16) -> u16 {
+    const HIDE_BELOW: u16 = 70;
+    const SHRINK_BELOW: u16 = 110;
+    const MIN_WIDTH: u16 = 24;
+
+    if total < HIDE_BELOW {
+        0
+    } else if total < SHRINK_BELOW {
+        (total * 30 / 100).clamp(MIN_WIDTH.min(preferred), preferred)
+    } else {
+        preferred
+    }
+}
+
+/// A rectangle of the given percentage size centered within `area`.
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Seven day columns for the week containing the current date, optionally
+/// grouped into time-of-day buckets.
+fn render_week(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let week = app.current_date.week(Weekday::Mon);
+    let today = app.config.today();
+    let time_format = app.config.time_format;
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 7); 7])
+        .split(area);
+
+    for (offset, column) in columns.iter().enumerate() {
+        let day = week.first_day() + chrono::Duration::days(offset as i64);
+        let mut tasks: Vec<&Task> = app
+            .data
+            .tasks
+            .iter()
+            .filter(|t| t.date == Some(day) && !t.is_heading())
+            .collect();
+        tasks.sort_by_key(|t| (TimeBucket::for_time(t.start_time), t.start_time));
+
+        let mut lines = Vec::new();
+        let mut bucket = None;
+        for task in tasks {
+            let task_bucket = TimeBucket::for_time(task.start_time);
+            if app.config.week_buckets && bucket != Some(task_bucket) {
+                if bucket.is_some() {
+                    lines.push(Line::from(""));
+                }
+                lines.push(Line::from(Span::styled(
+                    task_bucket.label(),
+                    Style::default().fg(Color::LightCyan).bold(),
+                )));
+                bucket = Some(task_bucket);
+            }
+            let style = if task.is_done() {
+                Style::default().fg(Color::DarkGray).crossed_out()
+            } else if task.start_time.is_some_and(|t| !app.config.within_work_hours(t)) {
+                // Dim anything scheduled outside working hours
+                Style::default().fg(Color::Gray).italic()
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let time = task
+                .start_time
+                .map(|t| format!("{} ", format_time(t, time_format)))
+                .unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled(time, Style::default().fg(Color::Cyan)),
+                Span::styled(task.title().to_string(), style),
+            ]));
+        }
+
+        let border_color = if day == today { app.config.accent(ViewMode::Week) } else { Color::Rgb(100, 100, 120) };
+        let title_style = if day == app.current_date {
+            Style::default().fg(Color::Yellow).bold()
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let column_widget = Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(app.config.border_set())
+                    .border_style(Style::default().fg(border_color))
+                    .title(Span::styled(day.format(" %a %d ").to_string(), title_style)),
+            );
+        f.render_widget(column_widget, *column);
+    }
+}
+
+fn render_bulk_preview(f: &mut ratatui::Frame, app: &App, preview: &BulkPreview) {
+    let (title, color) = match preview.op {
+        BulkOp::RollOverOverdue => (
+            format!("  Move {} overdue task(s) to today?  ", preview.targets.len()),
+            Color::Cyan,
+        ),
+        BulkOp::ClearCompleted => (
+            format!("  Delete {} completed task(s)?  ", preview.targets.len()),
+            Color::Red,
+        ),
+        BulkOp::ScheduleInbox => (
+            format!(
+                "  Schedule {} inbox task(s), {} per day?  ",
+                preview.targets.len(),
+                app.config.daily_capacity
+            ),
+            Color::Green,
+        ),
+        BulkOp::DeleteTasks => (
+            format!("  Delete {} task(s)?  ", preview.targets.len()),
+            Color::Red,
+        ),
+        BulkOp::CompleteAll => (
+            format!("  Mark {} task(s) done?  ", preview.targets.len()),
+            Color::Green,
+        ),
+        BulkOp::ReopenAll => (
+            format!("  Mark {} task(s) not done?  ", preview.targets.len()),
+            Color::Yellow,
+        ),
+    };
+
+    let mut lines: Vec<Line> = preview
+        .targets
+        .iter()
+        .enumerate()
+        .map(|(i, &idx)| {
+            let task = &app.data.tasks[idx];
+            // Show where the task is headed when there's a plan, else where it is
+            let date_str = preview
+                .planned
+                .get(i)
+                .copied()
+                .or(task.date)
+                .map(|d| d.format("%b %d").to_string())
+                .unwrap_or_else(|| "------".to_string());
+            Line::from(vec![
+                Span::styled(format!(" {} ", date_str), Style::default().fg(Color::DarkGray)),
+                Span::styled(task.title().to_string(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Confirm  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Cancel"),
+    ]));
+
+    let area = centered_rect(60, 60, f.area());
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(color))
+            .title(Span::styled(title, Style::default().fg(color).bold()))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_link_picker(f: &mut ratatui::Frame, app: &App, urls: &[String]) {
+    let mut lines: Vec<Line> = urls
+        .iter()
+        .take(9)
+        .enumerate()
+        .map(|(i, url)| {
+            Line::from(vec![
+                Span::styled(format!(" {} ", i + 1), Style::default().bg(Color::Blue).fg(Color::White)),
+                Span::raw(" "),
+                Span::styled(url.clone(), Style::default().fg(Color::Cyan)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" 1-9 ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Open  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Cancel"),
+    ]));
+
+    let area = centered_rect(60, 40, f.area());
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Blue))
+            .title(Span::styled(
+                format!("  {}Open Link  ", app.config.glyph("🔗 ", "")),
+                Style::default().fg(Color::Blue).bold(),
+            ))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_free_slots(f: &mut ratatui::Frame, app: &App, slots: &[(NaiveTime, NaiveTime)]) {
+    let time_format = app.config.time_format;
+    let mut lines: Vec<Line> = if slots.is_empty() {
+        vec![Line::from(Span::styled(
+            "  No free time within working hours",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        slots
+            .iter()
+            .map(|&(start, end)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "  {}{}{}",
+                            format_time(start, time_format),
+                            app.config.glyph("–", "-"),
+                            format_time(end, time_format)
+                        ),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        format!("  ({} free)", format_duration(end - start)),
+                        Style::default().fg(Color::Green),
+                    ),
+                ])
+            })
+            .collect()
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Close"),
+    ]));
+
+    let title = format!(
+        "  {}Free Time, {}{}{}  ",
+        app.config.glyph("🕓 ", ""),
+        format_time(app.config.work_start, time_format),
+        app.config.glyph("–", "-"),
+        format_time(app.config.work_end, time_format)
+    );
+    let area = centered_rect(50, 40, f.area());
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Green))
+            .title(Span::styled(title, Style::default().fg(Color::Green).bold()))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_search(f: &mut ratatui::Frame, app: &App, search: &Search) {
+    let area = centered_rect(70, 70, f.area());
+    let notes_flag = if search.include_notes { "on" } else { "off" };
+    let config = &app.config;
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(config.glyph(" 🔍 ", " / "), Style::default().fg(Color::Yellow)),
+            Span::styled(search.query.as_str(), Style::default().fg(Color::White).bold()),
+            Span::styled(config.glyph("█", "_"), Style::default().fg(Color::White)),
+        ]),
+        Line::from(Span::styled(
+            format!(" {} match(es)  {}  notes: {}", search.hits.len(), config.glyph("•", "|"), notes_flag),
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+    ];
+
+    // Keep the selected hit in view when there are more than fit
+    let visible = area.height.saturating_sub(7) as usize;
+    let offset = (search.selected + 1).saturating_sub(visible);
+    let notes = &app.notes_buffer;
+    for (i, hit) in search.hits.iter().enumerate().skip(offset).take(visible) {
+        let (marker, label, text) = match *hit {
+            SearchHit::Task(idx) => {
+                let task = &app.data.tasks[idx];
+                let date_str = task
+                    .date
+                    .map(|d| d.format("%b %d").to_string())
+                    .unwrap_or_else(|| "No date".to_string());
+                (config.glyph("✓", "T"), date_str, task.title().to_string())
+            }
+            SearchHit::Note(offset) => {
+                let start = notes[..offset].rfind('\n').map(|p| p + 1).unwrap_or(0);
+                let end = notes[offset..].find('\n').map(|p| offset + p).unwrap_or(notes.len());
+                (config.glyph("📝", "N"), "Notes".to_string(), notes[start..end].trim().to_string())
+            }
+        };
+
+        let line = Line::from(vec![
+            Span::raw(" "),
+            Span::styled(marker, Style::default().fg(Color::Cyan)),
+            Span::styled(format!(" {:<8} ", label), Style::default().fg(Color::DarkGray)),
+            Span::styled(text, Style::default().fg(Color::White)),
+        ]);
+        lines.push(if i == search.selected {
+            line.style(Style::default().bg(Color::Rgb(40, 40, 60)).bold())
+        } else {
+            line
+        });
+    }
+
+    let controls = Line::from(vec![
+        Span::styled(config.glyph(" ↑ ↓ ", " Up/Down "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Navigate  "),
+        Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Go  "),
+        Span::styled(" Tab ", Style::default().bg(Color::Magenta).fg(Color::White)),
+        Span::raw(" Notes on/off  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Close "),
+    ]);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(Span::styled("  Search  ", Style::default().fg(Color::Yellow).bold()))
+            .title_alignment(Alignment::Left)
+            .title_bottom(controls),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_template_picker(f: &mut ratatui::Frame, app: &App, selected: usize) {
+    let time_format = app.config.time_format;
+    let mut lines: Vec<Line> = if app.data.templates.is_empty() {
+        vec![Line::from(Span::styled(
+            "  No templates yet - press a to save the selected task as one",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        app.data
+            .templates
+            .iter()
+            .enumerate()
+            .map(|(i, template)| {
+                let times = match (template.start_time, template.end_time) {
+                    (Some(start), Some(end)) => format!(
+                        "{}-{}",
+                        format_time(start, time_format),
+                        format_time(end, time_format)
+                    ),
+                    (Some(start), None) => format_time(start, time_format),
+                    _ => "--:--".to_string(),
+                };
+                let line = Line::from(vec![
+                    Span::styled(format!("  {:<14} ", times), Style::default().fg(Color::Cyan)),
+                    Span::styled(template.name.clone(), Style::default().fg(Color::White)),
+                    Span::styled(if template.routine { app.config.glyph("  ↻ routine", "  (routine)") } else { "" }, Style::default().fg(Color::Green)),
+                ]);
+                if i == selected {
+                    line.style(Style::default().bg(Color::Rgb(40, 40, 60)).bold())
+                } else {
+                    line
+                }
+            })
+            .collect()
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Use  "),
+        Span::styled(" a ", Style::default().bg(Color::Blue).fg(Color::White)),
+        Span::raw(" Add Selected Task  "),
+        Span::styled(" r ", Style::default().bg(Color::Green).fg(Color::Black)),
+        Span::raw(" Routine  "),
+        Span::styled(" d ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Delete  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Close"),
+    ]));
+
+    let area = centered_rect(60, 50, f.area());
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Green))
+            .title(Span::styled(
+                format!("  {}Templates  ", app.config.glyph("📋 ", "")),
+                Style::default().fg(Color::Green).bold(),
+            ))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_date_picker(f: &mut ratatui::Frame, app: &App, selected: usize) {
+    let area = centered_rect(50, 60, f.area());
+    let today = app.config.today();
+    let dates = app.data.task_dates();
+
+    // Keep the selected date in view when there are more than fit
+    let visible = area.height.saturating_sub(4) as usize;
+    let offset = (selected + 1).saturating_sub(visible);
+    let mut lines: Vec<Line> = dates
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(i, &(date, total, done))| {
+            let date_style = if date == today {
+                Style::default().fg(Color::Cyan).bold()
+            } else if date < today {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let line = Line::from(vec![
+                Span::styled(format!("  {}  ", date.format("%a %b %d %Y")), date_style),
+                Span::styled(format!("{} task(s)", total), Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    if done > 0 { format!(", {} done", done) } else { String::new() },
+                    Style::default().fg(Color::Green),
+                ),
+            ]);
+            if i == selected {
+                line.style(Style::default().bg(Color::Rgb(40, 40, 60)).bold())
+            } else {
+                line
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(app.config.glyph(" ↑ ↓ ", " Up/Down "), Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Navigate  "),
+        Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Jump  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Cancel"),
+    ]));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(Span::styled(
+                format!("  {}Jump to Date ({})  ", app.config.glyph("📅 ", ""), dates.len()),
+                Style::default().fg(Color::Cyan).bold(),
+            ))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_trash(f: &mut ratatui::Frame, app: &App, selected: usize) {
+    let area = centered_rect(60, 60, f.area());
+    let today = app.config.today();
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                handle_input(app, key)?;
+    // Keep the selected task in view when there are more than fit
+    let visible = area.height.saturating_sub(4) as usize;
+    let offset = (selected + 1).saturating_sub(visible);
+    let mut lines: Vec<Line> = app
+        .data
+        .trash
+        .iter()
+        .rev()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(i, trashed)| {
+            let date_str = trashed
+                .task
+                .date
+                .map(|d| d.format("%a %b %d  ").to_string())
+                .unwrap_or_else(|| "No date     ".to_string());
+            let age = (today - trashed.deleted_at.date()).num_days();
+            let line = Line::from(vec![
+                Span::styled(format!("  {}", date_str), Style::default().fg(Color::Yellow)),
+                Span::raw(truncate(trashed.task.title(), 40)),
+                Span::styled(
+                    match age {
+                        0 => "  deleted today".to_string(),
+                        days => format!("  deleted {}d ago", days),
+                    },
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]);
+            if i == selected {
+                line.style(Style::default().bg(Color::Rgb(40, 40, 60)).bold())
+            } else {
+                line
             }
-        }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Enter ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Restore  "),
+        Span::styled(" d ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Delete  "),
+        Span::styled(" E ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Empty  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Close"),
+    ]));
 
-        if app.should_quit {
-            break;
-        }
-    }
-    Ok(())
+    let title = match app.config.trash_days {
+        Some(days) => format!("  {}Trash ({}), kept {} days  ", app.config.glyph("🗑  ", ""), app.data.trash.len(), days),
+        None => format!("  {}Trash ({})  ", app.config.glyph("🗑  ", ""), app.data.trash.len()),
+    };
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Red))
+            .title(Span::styled(title, Style::default().fg(Color::Red).bold()))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// The rows of the notes outline that fit in `visible` lines, scrolled to
+/// keep the selection in view.
+fn outline_lines(app: &App, selected: usize, visible: usize) -> Vec<Line<'_>> {
+    let texts: Vec<&str> = app.notes_buffer.split('\n').collect();
+    let gutter_width = texts.len().to_string().len();
+    let offset = (selected + 1).saturating_sub(visible);
+    let accent = app.config.accent(ViewMode::Notes);
+    notes_outline(&app.notes_buffer, &app.notes_folded)
+        .into_iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(i, row)| {
+            let mut spans = Vec::new();
+            if app.config.notes_line_numbers {
+                spans.push(Span::styled(
+                    format!("{:>width$} {} ", row.line + 1, app.config.glyph("│", "|"), width = gutter_width),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            let text = texts[row.line];
+            match (row.level, row.folded) {
+                (Some(_), Some(hidden)) => {
+                    spans.push(Span::styled(format!("{}{}", app.config.glyph("▸ ", "> "), text), Style::default().fg(accent).bold()));
+                    spans.push(Span::styled(format!("  ({} lines)", hidden), Style::default().fg(Color::DarkGray)));
+                }
+                (Some(_), None) => {
+                    spans.push(Span::styled(format!("{}{}", app.config.glyph("▾ ", "v "), text), Style::default().fg(accent).bold()));
+                }
+                _ => spans.push(Span::raw(format!("  {}", text))),
+            }
+            let line = Line::from(spans);
+            if i == selected {
+                line.style(Style::default().bg(Color::Rgb(40, 40, 60)))
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+fn render_review(f: &mut ratatui::Frame, app: &App, review: &Review) {
+    let area = centered_rect(60, 40, f.area());
+    let Some(task) = app.review_task().map(|idx| &app.data.tasks[idx]) else {
+        return;
+    };
+    let time = match (task.start_time, task.end_time) {
+        (Some(start), Some(end)) => format!(
+            "{}{}{}  ",
+            format_time(start, app.config.time_format),
+            app.config.glyph("–", "-"),
+            format_time(end, app.config.time_format)
+        ),
+        (Some(start), None) => format!("{}  ", format_time(start, app.config.time_format)),
+        _ => String::new(),
+    };
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("  {}", time), Style::default().fg(Color::Yellow)),
+            Span::styled(task.title().to_string(), Style::default().bold()),
+        ]),
+    ];
+    lines.extend(task.content.lines().skip(1).map(|line| Line::from(format!("  {}", line))));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Space ", Style::default().bg(Color::Green).fg(Color::Black).bold()),
+        Span::raw(" Done  "),
+        Span::styled(" t ", Style::default().bg(Color::Cyan).fg(Color::Black)),
+        Span::raw(" Tomorrow  "),
+        Span::styled(" d ", Style::default().bg(Color::Red).fg(Color::White)),
+        Span::raw(" Delete  "),
+        Span::styled(" k ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Keep  "),
+        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Stop"),
+    ]));
+
+    let position = review.total - review.queue.len() + 1;
+    let title = format!("  Review {} ({}/{})  ", review.date.format("%a, %b %d"), position, review.total);
+    let popup = Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(Span::styled(title, Style::default().fg(Color::Cyan).bold()))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn render_about(f: &mut ratatui::Frame, app: &App) {
+    let area = centered_rect(60, 50, f.area());
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("  Keep ", Style::default().fg(Color::Cyan).bold()),
+            Span::raw(format!("v{}", env!("CARGO_PKG_VERSION"))),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("  Recent changes:", Style::default().fg(Color::Yellow))),
+    ];
+    lines.extend(
+        RECENT_CHANGES
+            .iter()
+            .map(|change| Line::from(format!("  {} {}", app.config.glyph("•", "-"), change))),
+    );
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(" Esc ", Style::default().bg(Color::Rgb(80, 80, 100)).fg(Color::White)),
+        Span::raw(" Close"),
+    ]));
+
+    let popup = Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(app.config.border_set())
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(Span::styled("  About  ", Style::default().fg(Color::Cyan).bold()))
+            .title_alignment(Alignment::Left),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
 }
 
 fn handle_input(app: &mut App, key: KeyEvent) -> io::Result<()> {
-    if app.view_mode == ViewMode::Notes && !app.input_mode {
+    // Status messages only live until the next key press
+    app.status_message = None;
+
+    if let Some(prompt) = app.prompt.as_mut() {
+        let filtering = prompt.kind == PromptKind::Filter;
         match key.code {
-            KeyCode::Char('s') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                app.save_notes();
+            KeyCode::Esc => {
+                app.prompt = None;
+                if filtering {
+                    app.clear_filter();
+                }
             }
-            KeyCode::Char('q') => app.should_quit = true,
-            KeyCode::Tab => app.toggle_view(),
+            KeyCode::Enter => app.submit_prompt(),
+            KeyCode::Backspace => {
+                prompt.buffer.pop();
+            }
+            KeyCode::Char(c) => prompt.buffer.push(c),
+            _ => {}
+        }
+        // The filter narrows the list with every key
+        if let Some(prompt) = app.prompt.as_ref().filter(|_| filtering) {
+            app.list_filter = Some(prompt.buffer.clone());
+            app.selected_task = 0;
+        }
+    } else if let Some(selected) = app.template_picker {
+        let count = app.data.templates.len();
+        match key.code {
+            KeyCode::Esc => app.template_picker = None,
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => app.template_picker = Some(selected - 1),
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => {
+                app.template_picker = Some(selected + 1);
+            }
+            KeyCode::Enter if selected < count => {
+                app.template_picker = None;
+                app.start_add_from_template(selected);
+            }
+            KeyCode::Char('a') => app.save_selected_as_template(),
+            KeyCode::Char('r') => app.toggle_routine(selected),
+            KeyCode::Char('d') => app.delete_template(selected),
+            _ => {}
+        }
+    } else if let Some(selected) = app.date_picker {
+        let count = app.data.task_dates().len();
+        match key.code {
+            KeyCode::Esc => app.date_picker = None,
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => app.date_picker = Some(selected - 1),
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => app.date_picker = Some(selected + 1),
+            KeyCode::Enter => app.jump_to_picked_date(selected),
+            _ => {}
+        }
+    } else if let Some(selected) = app.trash_picker {
+        let count = app.data.trash.len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('X') => app.trash_picker = None,
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => app.trash_picker = Some(selected - 1),
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => app.trash_picker = Some(selected + 1),
+            KeyCode::Enter | KeyCode::Char('d' | 'E') if app.read_only => app.deny_read_only(),
+            KeyCode::Enter => app.restore_trashed(selected),
+            KeyCode::Char('d') => app.purge_trashed(selected),
+            KeyCode::Char('E') => app.empty_trash(),
+            _ => {}
+        }
+    } else if app.review.is_some() {
+        match key.code {
+            KeyCode::Esc => app.finish_review(),
+            KeyCode::Char('k') | KeyCode::Enter => app.decide_review(ReviewDecision::Keep),
+            KeyCode::Char('t') => app.decide_review(ReviewDecision::Tomorrow),
+            KeyCode::Char('d') => app.decide_review(ReviewDecision::Delete),
+            KeyCode::Char(' ') => app.decide_review(ReviewDecision::Done),
+            _ => {}
+        }
+    } else if app.show_about {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('V' | 'q')) {
+            app.show_about = false;
+        }
+    } else if let Some(search) = app.search.as_mut() {
+        match key.code {
+            KeyCode::Esc => app.search = None,
             KeyCode::Enter => {
-                app.notes_buffer.insert(app.notes_cursor, '\n');
-                app.notes_cursor += 1;
+                if let Some(&hit) = search.hits.get(search.selected) {
+                    app.last_search = app.search.take();
+                    app.jump_to_hit(hit);
+                }
             }
-            KeyCode::Char(c) => {
-                app.notes_buffer.insert(app.notes_cursor, c);
-                app.notes_cursor += 1;
+            KeyCode::Up if search.selected > 0 => search.selected -= 1,
+            KeyCode::Down if search.selected + 1 < search.hits.len() => search.selected += 1,
+            KeyCode::Tab => {
+                search.include_notes = !search.include_notes;
+                app.update_search();
             }
             KeyCode::Backspace => {
-                if app.notes_cursor > 0 {
-                    app.notes_cursor -= 1;
-                    app.notes_buffer.remove(app.notes_cursor);
-                }
+                search.query.pop();
+                app.update_search();
             }
-            KeyCode::Delete => {
-                if app.notes_cursor < app.notes_buffer.len() {
-                    app.notes_buffer.remove(app.notes_cursor);
-                }
+            KeyCode::Char(c) => {
+                search.query.push(c);
+                app.update_search();
             }
-            KeyCode::Left => {
-                if app.notes_cursor > 0 {
-                    app.notes_cursor -= 1;
+            _ => {}
+        }
+    } else if let Some(urls) = &app.link_picker {
+        match key.code {
+            KeyCode::Char(c @ '1'..='9') => {
+                let choice = urls.get(c as usize - '1' as usize).cloned();
+                if let Some(url) = choice {
+                    app.link_picker = None;
+                    app.open_url(&url);
                 }
             }
-            KeyCode::Right => {
-                if app.notes_cursor < app.notes_buffer.len() {
-                    app.notes_cursor += 1;
+            KeyCode::Esc => app.link_picker = None,
+            _ => {}
+        }
+    } else if app.free_slots.is_some() {
+        // Any key closes the free time list
+        app.free_slots = None;
+    } else if app.bulk_preview.is_some() {
+        match key.code {
+            KeyCode::Enter => app.apply_bulk(),
+            KeyCode::Esc => app.bulk_preview = None,
+            _ => {}
+        }
+    } else if let Some(selected) = app.notes_outline.filter(|_| app.view_mode == ViewMode::Notes) {
+        let count = notes_outline(&app.notes_buffer, &app.notes_folded).len();
+        match key.code {
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.toggle_outline(),
+            KeyCode::Esc => app.toggle_outline(),
+            KeyCode::Char('q') => app.request_quit(),
+            KeyCode::Tab => app.toggle_view(),
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => app.notes_outline = Some(selected - 1),
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => app.notes_outline = Some(selected + 1),
+            KeyCode::Enter | KeyCode::Char(' ' | 'z') => app.toggle_fold(selected),
+            _ => {}
+        }
+    } else if app.view_mode == ViewMode::Notes && !app.input_mode {
+        app.notes_edited_at = Some(Instant::now());
+        match key.code {
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => app.toggle_outline(),
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.save_notes();
+            }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if app.read_only {
+                    app.deny_read_only();
+                } else {
+                    app.edit_notes_externally = true;
                 }
             }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => app.toggle_line_numbers(),
+            KeyCode::Char('q') => app.request_quit(),
+            KeyCode::Tab => app.toggle_view(),
+            KeyCode::Enter | KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete if app.read_only => {
+                app.deny_read_only();
+            }
+            KeyCode::Enter => app.notes_newline(),
+            KeyCode::Char(c) => app.notes_insert_char(c),
+            KeyCode::Backspace if app.notes_cursor > 0 => {
+                app.notes_cursor -= 1;
+                app.notes_buffer.remove(app.notes_cursor);
+            }
+            KeyCode::Delete if app.notes_cursor < app.notes_buffer.len() => {
+                app.notes_buffer.remove(app.notes_cursor);
+            }
+            KeyCode::Left if app.notes_cursor > 0 => {
+                app.notes_cursor -= 1;
+            }
+            KeyCode::Right if app.notes_cursor < app.notes_buffer.len() => {
+                app.notes_cursor += 1;
+            }
             KeyCode::Up => {
                 // Move cursor up one line
                 let before_cursor = &app.notes_buffer[..app.notes_cursor];
@@ -813,84 +5747,871 @@ fn handle_input(app: &mut App, key: KeyEvent) -> io::Result<()> {
             }
             _ => {}
         }
+    } else if app.sidebar_focused {
+        match key.code {
+            KeyCode::Char('q') => app.request_quit(),
+            KeyCode::Esc | KeyCode::Char('O') => app.toggle_sidebar_focus(),
+            KeyCode::Up | KeyCode::Char('k') => app.prev_overdue(),
+            KeyCode::Down | KeyCode::Char('j') => app.next_overdue(),
+            KeyCode::Char(' ' | 'r' | 'R' | 's') if app.read_only => app.deny_read_only(),
+            KeyCode::Char(' ') => app.complete_overdue(),
+            KeyCode::Char('r') => app.roll_over_overdue(),
+            KeyCode::Char('R') => app.preview_bulk(BulkOp::RollOverOverdue),
+            KeyCode::Char('s') => app.snooze_overdue(),
+            _ => {}
+        }
     } else if app.input_mode {
+        let max_time_len = app.max_time_len();
         match key.code {
+            // Not every terminal reports Shift+Enter, so Alt+Enter works too
+            KeyCode::Enter
+                if app.time_input_field == 0 && key.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+            {
+                app.input_buffer.insert(app.input_cursor, '\n');
+                app.input_cursor += 1;
+            }
             KeyCode::Enter => app.add_task(),
+            KeyCode::Esc if app.content_suggestion().is_some() => app.suggestion_dismissed = true,
+            KeyCode::Tab | KeyCode::Right if app.accept_suggestion() => {}
             KeyCode::Esc => {
                 app.input_mode = false;
                 app.time_input_field = 0;
                 app.editing_task_idx = None;
                 app.input_buffer.clear();
+                app.input_cursor = 0;
                 app.start_time_buffer.clear();
                 app.end_time_buffer.clear();
+                app.date_buffer.clear();
+                app.label_input = None;
+                app.reminder_buffer.clear();
+                app.location_buffer.clear();
+                app.blocked_by_input = None;
             }
             KeyCode::Tab => {
-                app.time_input_field = (app.time_input_field + 1) % 3;
+                app.time_input_field = (app.time_input_field + 1) % 8;
             }
+            KeyCode::Right | KeyCode::Char(' ') if app.time_input_field == 4 => app.cycle_label(true),
+            KeyCode::Left if app.time_input_field == 4 => app.cycle_label(false),
+            KeyCode::Right | KeyCode::Char(' ') if app.time_input_field == 7 => app.cycle_blocker(true),
+            KeyCode::Left if app.time_input_field == 7 => app.cycle_blocker(false),
             KeyCode::Char(c) => {
                 match app.time_input_field {
-                    0 => app.input_buffer.push(c),
-                    1 => {
-                        if app.start_time_buffer.len() < 5 && (c.is_ascii_digit() || c == ':') {
-                            app.start_time_buffer.push(c);
-                        }
+                    0 => {
+                        app.input_buffer.insert(app.input_cursor, c);
+                        app.input_cursor += c.len_utf8();
+                        app.suggestion_dismissed = false;
                     }
-                    2 => {
-                        if app.end_time_buffer.len() < 5 && (c.is_ascii_digit() || c == ':') {
-                            app.end_time_buffer.push(c);
-                        }
+                    1 if app.start_time_buffer.len() < max_time_len && app.is_time_char(c) => {
+                        app.start_time_buffer.push(c);
+                    }
+                    // The end can also be a duration like `+1h30m`
+                    2 if app.end_time_buffer.len() < max_time_len.max(8)
+                        && (app.is_time_char(c) || matches!(c, '+' | 'h' | 'm')) =>
+                    {
+                        app.end_time_buffer.push(c);
+                    }
+                    3 if app.date_buffer.len() < MAX_DATE_INPUT_LEN && is_date_char(c) => {
+                        app.date_buffer.push(c);
+                    }
+                    5 if app.reminder_buffer.len() < 8 && (c.is_ascii_digit() || matches!(c, 'h' | 'm')) => {
+                        app.reminder_buffer.push(c);
                     }
+                    6 => app.location_buffer.push(c),
                     _ => {}
                 }
             }
+            KeyCode::Delete if app.time_input_field == 0 && app.input_cursor < app.input_buffer.len() => {
+                app.input_buffer.remove(app.input_cursor);
+            }
+            KeyCode::Left if app.time_input_field == 0 => {
+                if let Some(prev) = app.input_buffer[..app.input_cursor].chars().next_back() {
+                    app.input_cursor -= prev.len_utf8();
+                }
+            }
+            KeyCode::Right if app.time_input_field == 0 => {
+                if let Some(next) = app.input_buffer[app.input_cursor..].chars().next() {
+                    app.input_cursor += next.len_utf8();
+                }
+            }
+            KeyCode::Home if app.time_input_field == 0 => app.input_cursor = 0,
+            KeyCode::End if app.time_input_field == 0 => app.input_cursor = app.input_buffer.len(),
             KeyCode::Backspace => {
                 match app.time_input_field {
-                    0 => { app.input_buffer.pop(); }
+                    0 => {
+                        if let Some(prev) = app.input_buffer[..app.input_cursor].chars().next_back() {
+                            app.input_cursor -= prev.len_utf8();
+                            app.input_buffer.remove(app.input_cursor);
+                            app.suggestion_dismissed = false;
+                        }
+                    }
                     1 => { app.start_time_buffer.pop(); }
                     2 => { app.end_time_buffer.pop(); }
+                    3 => { app.date_buffer.pop(); }
+                    5 => { app.reminder_buffer.pop(); }
+                    6 => { app.location_buffer.pop(); }
                     _ => {}
                 }
             }
             _ => {}
         }
     } else {
-        match key.code {
-            KeyCode::Char('q') => app.should_quit = true,
-            KeyCode::Char('n') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.input_mode = true;
+        let is_task_view = matches!(app.view_mode, ViewMode::Scheduled | ViewMode::All);
+
+        // Digits build up a count for the next motion; 0 only continues one
+        if let KeyCode::Char(c @ '0'..='9') = key.code {
+            if is_task_view && (c != '0' || app.pending_count.is_some()) {
+                let digit = c as usize - '0' as usize;
+                let count = (app.pending_count.unwrap_or(0) * 10 + digit).min(9999);
+                app.pending_count = Some(count);
+                app.status_message = Some(format!("Count: {}", count));
+                return Ok(());
+            }
+        }
+        let count = app.pending_count.take();
+        let repeat = count.unwrap_or(1);
+
+        // Finish a two-key sequence, or fall through and treat the key normally
+        if let Some((first, pressed_at)) = app.pending_key.take() {
+            if pressed_at.elapsed() <= PENDING_KEY_TIMEOUT {
+                match (first, key.code) {
+                    ('d', KeyCode::Char('d')) => {
+                        app.delete_tasks(repeat);
+                        return Ok(());
+                    }
+                    ('g', KeyCode::Char('g')) => {
+                        app.select_first_task();
+                        return Ok(());
+                    }
+                    _ => {}
                 }
             }
-            KeyCode::Char('e') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.start_edit_task();
+        }
+
+        match key.code {
+            KeyCode::Char('q') => app.request_quit(),
+            // While a search is active `n`/`N` step through its matches, like in vim
+            KeyCode::Char('n') if app.last_search.is_some() => app.step_search(true),
+            KeyCode::Char('N') if app.last_search.is_some() => app.step_search(false),
+            KeyCode::Esc if app.last_search.is_some() => {
+                app.last_search = None;
+                app.status_message = Some("Search cleared".to_string());
+            }
+            KeyCode::Esc if app.list_filter.is_some() => {
+                app.clear_filter();
+                app.status_message = Some("Filter cleared".to_string());
+            }
+            KeyCode::Char('n' | '#' | '.' | 'e' | ' ' | 'd' | 'p' | 's' | 'i' | 'T' | 'R' | 'C' | 'P' | 'S' | 'A' | 'w' | '<' | '>' | '+' | '*' | 'v')
+                if app.read_only =>
+            {
+                app.deny_read_only();
+            }
+            KeyCode::Char('s') if app.read_only && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.deny_read_only();
+            }
+            KeyCode::Char('n') if is_task_view => app.start_add_task(),
+            KeyCode::Char('#') if is_task_view => app.start_add_heading(),
+            KeyCode::Char('.') if is_task_view => app.start_add_like_last(),
+            KeyCode::Char('e') if is_task_view => app.start_edit_task(),
+            KeyCode::Char(' ') if is_task_view => app.toggle_task(),
+            KeyCode::Char(c @ ('d' | 'g')) if is_task_view => {
+                app.pending_key = Some((c, Instant::now()));
+                // The count applies to the whole sequence, e.g. `3dd`
+                app.pending_count = count;
+            }
+            // `G` jumps to the last task, or with a count to that task, e.g. `5G`
+            KeyCode::Char('G') if is_task_view => match count {
+                Some(n) => {
+                    app.selected_task = n.saturating_sub(1);
+                    app.clamp_selection();
+                }
+                None => app.select_last_task(),
+            },
+            KeyCode::Char('p') if is_task_view => app.toggle_pin(),
+            KeyCode::Char('*') if is_task_view => app.toggle_focus(),
+            KeyCode::Char('>') if is_task_view => app.shift_task_date(1),
+            KeyCode::Char('<') if is_task_view => app.shift_task_date(-1),
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => app.save_now(),
+            KeyCode::Char('w') => app.save_now(),
+            KeyCode::Char('s') if is_task_view => app.snooze_selected(),
+            KeyCode::Char('+') if is_task_view => app.log_time_selected(),
+            KeyCode::Char('i') if is_task_view => app.cycle_status(),
+            KeyCode::Char('O') => app.toggle_sidebar_focus(),
+            KeyCode::Char('b') => app.toggle_sidebar(),
+            KeyCode::Char('[') => app.resize_sidebar(-5),
+            KeyCode::Char(']') => app.resize_sidebar(5),
+            KeyCode::Char('D') => app.toggle_density(),
+            KeyCode::Char('m') => app.toggle_one_line_rows(),
+            KeyCode::Char('M') => app.toggle_compact_header(),
+            KeyCode::Char('o') if is_task_view => app.open_task_link(),
+            KeyCode::Char('z') if is_task_view => app.toggle_show_done(),
+            KeyCode::Char('F') if is_task_view => app.cycle_time_filter(),
+            KeyCode::Char('f') if app.view_mode == ViewMode::Scheduled => app.show_free_slots(),
+            KeyCode::Char('y') if app.view_mode == ViewMode::Scheduled => app.copy_day(),
+            KeyCode::Char('/') => app.start_search(),
+            KeyCode::Char('&') if is_task_view => app.start_filter(),
+            KeyCode::Char('T') if is_task_view => app.template_picker = Some(0),
+            KeyCode::Char('J') if !app.today_only => app.open_date_picker(),
+            KeyCode::Char('X') => app.open_trash(),
+            KeyCode::Char('V') => app.show_about = true,
+            KeyCode::Char('v') if app.view_mode == ViewMode::Scheduled => app.start_review(),
+            KeyCode::Char('S') if app.view_mode == ViewMode::Scheduled => app.seed_routine(),
+            KeyCode::Char('R') => app.preview_bulk(BulkOp::RollOverOverdue),
+            KeyCode::Char('C') if is_task_view => app.preview_bulk(BulkOp::ClearCompleted),
+            KeyCode::Char('A') if is_task_view => app.toggle_all_tasks(),
+            KeyCode::Char('P') => app.preview_bulk(BulkOp::ScheduleInbox),
+            KeyCode::Tab => app.toggle_view(),
+            KeyCode::Up | KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) && is_task_view => {
+                if app.read_only {
+                    app.deny_read_only();
+                } else {
+                    app.move_task(key.code == KeyCode::Down);
                 }
             }
-            KeyCode::Char(' ') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.toggle_task();
+            KeyCode::Up | KeyCode::Char('k') => {
+                for _ in 0..repeat {
+                    app.prev_task();
                 }
             }
-            KeyCode::Char('d') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.delete_task();
+            KeyCode::Down | KeyCode::Char('j') => {
+                for _ in 0..repeat {
+                    app.next_task();
                 }
             }
-            KeyCode::Tab => app.toggle_view(),
-            KeyCode::Up | KeyCode::Char('k') => app.prev_task(),
-            KeyCode::Down | KeyCode::Char('j') => app.next_task(),
-            KeyCode::Left | KeyCode::Char('h') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.prev_day();
+            // --today pins the view to today
+            KeyCode::Left | KeyCode::Right | KeyCode::Char('h' | 'l' | 'H' | 'L') if app.today_only => {}
+            KeyCode::Left | KeyCode::Char('H')
+                if app.view_mode == ViewMode::Scheduled
+                    && (key.code == KeyCode::Char('H') || key.modifiers.contains(KeyModifiers::SHIFT)) =>
+            {
+                app.prev_busy_day();
+            }
+            KeyCode::Right | KeyCode::Char('L')
+                if app.view_mode == ViewMode::Scheduled
+                    && (key.code == KeyCode::Char('L') || key.modifiers.contains(KeyModifiers::SHIFT)) =>
+            {
+                app.next_busy_day();
+            }
+            KeyCode::Left | KeyCode::Char('h') if app.view_mode == ViewMode::Scheduled => {
+                app.prev_day();
+            }
+            KeyCode::Right | KeyCode::Char('l') if app.view_mode == ViewMode::Scheduled => {
+                app.next_day();
+            }
+            KeyCode::Left | KeyCode::Char('h') if app.view_mode == ViewMode::Week => app.shift_week(false),
+            KeyCode::Right | KeyCode::Char('l') if app.view_mode == ViewMode::Week => app.shift_week(true),
+            KeyCode::Char('t') if app.view_mode == ViewMode::Week => app.toggle_week_buckets(),
+            KeyCode::Char('W') if app.view_mode == ViewMode::Week => app.start_go_to_week(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn handle_paste(app: &mut App, text: &str) {
+    if app.input_mode {
+        match app.time_input_field {
+            0 => {
+                let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                app.input_buffer.insert_str(app.input_cursor, &text);
+                app.input_cursor += text.len();
+            }
+            1 | 2 => {
+                let is_end = app.time_input_field == 2;
+                let max_time_len = if is_end { app.max_time_len().max(8) } else { app.max_time_len() };
+                let accepted: String = text
+                    .trim()
+                    .chars()
+                    .filter(|&c| app.is_time_char(c) || (is_end && matches!(c, '+' | 'h' | 'm')))
+                    .collect();
+                let buffer = if is_end {
+                    &mut app.end_time_buffer
+                } else {
+                    &mut app.start_time_buffer
+                };
+                for c in accepted.chars() {
+                    if buffer.len() >= max_time_len {
+                        break;
+                    }
+                    buffer.push(c);
                 }
             }
-            KeyCode::Right | KeyCode::Char('l') => {
-                if app.view_mode == ViewMode::Scheduled {
-                    app.next_day();
+            3 => {
+                let accepted = text
+                    .trim()
+                    .chars()
+                    .filter(|&c| is_date_char(c));
+                for c in accepted {
+                    if app.date_buffer.len() >= MAX_DATE_INPUT_LEN {
+                        break;
+                    }
+                    app.date_buffer.push(c);
                 }
             }
+            6 => {
+                let text = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+                app.location_buffer.push_str(&text);
+            }
             _ => {}
         }
+    } else if app.view_mode == ViewMode::Notes && !app.read_only {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let room = app.notes_room();
+        let text: String = if text.chars().count() > room {
+            app.status_message = Some("Paste cut off at the notes_max_chars limit".to_string());
+            text.chars().take(room).collect()
+        } else {
+            text
+        };
+        app.notes_buffer.insert_str(app.notes_cursor, &text);
+        app.notes_edited_at = Some(Instant::now());
+        app.notes_cursor += text.len();
+    }
+}
+
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    // The mouse only drives the task list; popups and the form stay keyboard-only
+    let busy = app.input_mode
+        || app.prompt.is_some()
+        || app.template_picker.is_some()
+        || app.date_picker.is_some()
+        || app.trash_picker.is_some()
+        || app.show_about
+        || app.review.is_some()
+        || app.search.is_some()
+        || app.link_picker.is_some()
+        || app.free_slots.is_some()
+        || app.bulk_preview.is_some();
+    if busy || !matches!(app.view_mode, ViewMode::Scheduled | ViewMode::All) {
+        return;
+    }
+
+    match mouse.kind {
+        MouseEventKind::ScrollDown => app.next_task(),
+        MouseEventKind::ScrollUp => app.prev_task(),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let area = app.task_area;
+            // Below the top border and the header row (plus its margin when comfortable)
+            let header_height = if app.config.density == Density::Compact { 2 } else { 3 };
+            let first_row = area.y + header_height;
+            let inside = mouse.column > area.x
+                && mouse.column < area.right().saturating_sub(1)
+                && mouse.row >= first_row
+                && mouse.row < area.bottom().saturating_sub(1);
+            if !inside {
+                return;
+            }
+            let Some(position) = app.task_at_row((mouse.row - first_row) as usize) else {
+                return;
+            };
+            app.status_message = None;
+            app.sidebar_focused = false;
+            app.selected_task = position;
+            if app.checkbox_columns().contains(&(mouse.column - area.x - 1)) {
+                if app.read_only {
+                    app.deny_read_only();
+                } else {
+                    app.toggle_task();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn natural_date_keywords() {
+        let today = date(2025, 3, 14);
+        assert_eq!(parse_natural_date("today", today), Some(today));
+        assert_eq!(parse_natural_date("Tomorrow", today), Some(date(2025, 3, 15)));
+        assert_eq!(parse_natural_date("  yesterday ", today), Some(date(2025, 3, 13)));
+        assert_eq!(parse_natural_date("2025-12-01", today), Some(date(2025, 12, 1)));
+        assert_eq!(parse_natural_date("someday", today), None);
+    }
+
+    #[test]
+    fn natural_date_offsets() {
+        let today = date(2025, 2, 27);
+        assert_eq!(parse_natural_date("+2", today), Some(date(2025, 3, 1)));
+        assert_eq!(parse_natural_date("-27", today), Some(date(2025, 1, 31)));
+        assert_eq!(parse_natural_date("+", today), None);
+    }
+
+    #[test]
+    fn weekday_is_the_next_occurrence() {
+        // 2025-03-12 is a Wednesday
+        let today = date(2025, 3, 12);
+        assert_eq!(parse_natural_date("thu", today), Some(date(2025, 3, 13)));
+        assert_eq!(parse_natural_date("sunday", today), Some(date(2025, 3, 16)));
+        assert_eq!(parse_natural_date("next mon", today), Some(date(2025, 3, 17)));
+        assert_eq!(parse_natural_date("tue", today), Some(date(2025, 3, 18)));
+    }
+
+    #[test]
+    fn weekday_naming_today_means_next_week() {
+        // 2025-03-12 is a Wednesday
+        let today = date(2025, 3, 12);
+        assert_eq!(parse_natural_date("wed", today), Some(date(2025, 3, 19)));
+        assert_eq!(parse_natural_date("next wednesday", today), Some(date(2025, 3, 19)));
+    }
+
+    #[test]
+    fn weekday_wraps_across_month_and_year() {
+        // Sunday 2024-12-29 -> Monday/Saturday of the new year's first week
+        let today = date(2024, 12, 29);
+        assert_eq!(parse_natural_date("mon", today), Some(date(2024, 12, 30)));
+        assert_eq!(parse_natural_date("sat", today), Some(date(2025, 1, 4)));
+        assert_eq!(parse_natural_date("sun", today), Some(date(2025, 1, 5)));
+        // Saturday at the end of a leap February
+        assert_eq!(parse_natural_date("fri", date(2024, 2, 24)), Some(date(2024, 3, 1)));
+    }
+
+    fn task(content: &str, date: Option<NaiveDate>) -> Task {
+        Task {
+            id: 0,
+            content: content.to_string(),
+            status: Status::Todo,
+            date,
+            start_time: None,
+            end_time: None,
+            pinned: false,
+            kind: TaskKind::Task,
+            label: None,
+            reminder_minutes: None,
+            completed_at: None,
+            created_at: None,
+            logged_minutes: 0,
+            location: None,
+            focus: false,
+            blocked_by: None,
+        }
+    }
+
+    #[test]
+    fn merge_skips_duplicate_tasks() {
+        let day = Some(date(2025, 3, 14));
+        let mut data = AppData::new();
+        data.tasks = vec![task("Standup", day), task("Lunch", day)];
+        let mut other = AppData::new();
+        let mut later = task("Standup", day);
+        later.start_time = NaiveTime::from_hms_opt(9, 0, 0);
+        other.tasks = vec![task("Standup", day), later, task("Lunch", None), task("Gym", day), task("Gym", day)];
+
+        assert_eq!(data.merge(other), (3, 2));
+        let contents: Vec<&str> = data.tasks.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, ["Standup", "Lunch", "Standup", "Lunch", "Gym"]);
+    }
+
+    #[test]
+    fn merge_joins_notes() {
+        let mut data = AppData::new();
+        data.notes = "work ideas\n".to_string();
+        let mut other = AppData::new();
+        other.notes = "home ideas".to_string();
+        data.merge(other);
+        assert_eq!(data.notes, format!("work ideas\n\n{}\n\nhome ideas", MERGE_SEPARATOR));
+
+        // Merging the same notes again doesn't repeat them
+        let mut again = AppData::new();
+        again.notes = "home ideas".to_string();
+        data.merge(again);
+        assert_eq!(data.notes.matches("home ideas").count(), 1);
+
+        let mut empty = AppData::new();
+        let mut other = AppData::new();
+        other.notes = "only here".to_string();
+        empty.merge(other);
+        assert_eq!(empty.notes, "only here");
+    }
+
+    #[test]
+    fn merge_adds_new_templates_only() {
+        let template = |name: &str| Template {
+            name: name.to_string(),
+            content: name.to_string(),
+            start_time: None,
+            end_time: None,
+            routine: false,
+        };
+        let mut data = AppData::new();
+        data.templates = vec![template("Standup")];
+        let mut other = AppData::new();
+        other.templates = vec![template("Standup"), template("Review")];
+        data.merge(other);
+        let names: Vec<&str> = data.templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Standup", "Review"]);
+    }
+
+    #[test]
+    fn doctor_finds_and_repairs_problems() {
+        let day = Some(date(2025, 3, 14));
+        let mut reversed = task("Late shift", day);
+        reversed.start_time = NaiveTime::from_hms_opt(22, 0, 0);
+        reversed.end_time = NaiveTime::from_hms_opt(6, 0, 0);
+        let mut data = AppData::new();
+        data.tasks = vec![task("Standup", day), task("  ", day), reversed, task("Standup ", day), task("Standup", None)];
+
+        let diagnosis = data.diagnose();
+        assert_eq!(diagnosis, Diagnosis { empty: vec![1], reversed: vec![2], duplicates: vec![3] });
+
+        data.repair();
+        let contents: Vec<&str> = data.tasks.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, ["Standup", "Late shift", "Standup"]);
+        assert_eq!(data.tasks[1].end_time, None);
+        assert!(data.diagnose().is_healthy());
+    }
+
+    #[test]
+    fn config_falls_back_to_legacy_settings() {
+        let legacy = r#"{"tasks": [], "settings": {"time_format": "12h", "daily_capacity": 3}}"#;
+        let config = Config::from_legacy(legacy).unwrap();
+        assert_eq!(config.time_format, TimeFormat::TwelveHour);
+        assert_eq!(config.daily_capacity, 3);
+        assert!(config.autocomplete);
+
+        assert!(Config::from_legacy(r#"{"tasks": []}"#).is_none());
+        // Loading data written by older versions ignores the settings object
+        assert!(serde_json::from_str::<AppData>(legacy).is_ok());
+    }
+
+    #[test]
+    fn date_format_falls_back_when_unusable() {
+        let mut config = Config::default();
+        let day = date(2025, 3, 14);
+        config.date_format = "%d.%m.%Y".to_string();
+        assert_eq!(config.full_date(day), "14.03.2025");
+        for format in ["%H:%M", "%Z", "%Q"] {
+            config.date_format = format.to_string();
+            assert_eq!(config.full_date(day), "Friday, March 14, 2025");
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let day = Some(date(2025, 3, 14));
+        let mut timed = task("Standup, then \"retro\"", day);
+        timed.start_time = NaiveTime::from_hms_opt(9, 0, 0);
+        timed.end_time = NaiveTime::from_hms_opt(9, 15, 0);
+        timed.set_status(Status::Done);
+        let mut heading = task("Morning", day);
+        heading.kind = TaskKind::Heading;
+        let mut data = AppData::new();
+        data.tasks = vec![timed, heading, task("Pack\n- passport", None), task("Gym", day)];
+        let fields = |tasks: &[Task]| -> Vec<_> {
+            tasks
+                .iter()
+                .filter(|t| !t.is_heading())
+                .map(|t| (t.content.clone(), t.date, t.start_time, t.end_time, t.is_done()))
+                .collect()
+        };
+
+        for delimiter in [b',', b'\t'] {
+            let csv = data.to_csv(delimiter).unwrap();
+            assert!(csv.starts_with(&CSV_COLUMNS.join(&(delimiter as char).to_string())));
+            let mut imported = AppData::new();
+            let mut incoming = AppData::new();
+            incoming.tasks = AppData::tasks_from_csv(&csv, delimiter).unwrap();
+            assert_eq!(imported.merge(incoming), (3, 0));
+            assert_eq!(fields(&imported.tasks), fields(&data.tasks));
+        }
+
+        let edited = "content,completed,date\nCall Sam,yes,2025-03-15\n";
+        let tasks = AppData::tasks_from_csv(edited, b',').unwrap();
+        assert_eq!(fields(&tasks), [("Call Sam".to_string(), Some(date(2025, 3, 15)), None, None, true)]);
+        assert!(AppData::tasks_from_csv("content,completed\nCall Sam,maybe\n", b',').is_err());
+        assert_eq!(AppData::tasks_from_csv("content,date\nGym,\n,\n", b',').unwrap().len(), 1);
+        assert!(AppData::tasks_from_csv("content,date\n,2025-03-15\n", b',').is_err());
+        assert!(tasks.iter().all(|t| t.created_at.is_some()));
+        assert_eq!(csv_delimiter("week.TSV"), b'\t');
+    }
+
+    #[test]
+    fn switching_formats_keeps_the_latest_data() {
+        let dir = std::env::temp_dir().join(format!("keep-formats-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_str().unwrap();
+        let load = |format| AppData::load_from(&AppData::find_in(dir, format)).unwrap();
+
+        let mut data = AppData::new();
+        data.tasks = vec![task("Written as JSON", None)];
+        data.save_in(dir, StorageFormat::Json).unwrap();
+
+        let mut data = load(StorageFormat::Toml);
+        data.tasks.push(task("Added while on TOML", None));
+        data.save_in(dir, StorageFormat::Toml).unwrap();
+        assert!(std::path::Path::new(&format!("{}.bak", AppData::path_in(dir, StorageFormat::Json))).exists());
+
+        let data = load(StorageFormat::Json);
+        assert_eq!(data.tasks.len(), 2);
+        data.save_in(dir, StorageFormat::Json).unwrap();
+        assert_eq!(AppData::find_in(dir, StorageFormat::Toml), AppData::path_in(dir, StorageFormat::Json));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn storage_formats_round_trip() {
+        let day = Some(date(2025, 3, 14));
+        let mut timed = task("Standup", day);
+        timed.start_time = NaiveTime::from_hms_opt(9, 0, 0);
+        timed.end_time = NaiveTime::from_hms_opt(9, 15, 0);
+        timed.label = Some("work".to_string());
+        timed.reminder_minutes = Some(10);
+        timed.status = Status::Done;
+        timed.completed_at = day.and_then(|d| d.and_hms_opt(9, 20, 0));
+        let mut heading = task("Morning", day);
+        heading.kind = TaskKind::Heading;
+        let mut data = AppData::new();
+        data.tasks = vec![timed, heading, task("Someday \"quoted\"", None)];
+        data.assign_ids();
+        data.tasks[2].blocked_by = Some(data.tasks[0].id);
+        data.notes = "# Ideas\n- one\n\ttwo\n".to_string();
+        data.templates = vec![Template {
+            name: "Gym".to_string(),
+            content: "Gym".to_string(),
+            start_time: NaiveTime::from_hms_opt(18, 0, 0),
+            end_time: None,
+            routine: true,
+        }];
+
+        let expected = serde_json::to_string(&data).unwrap();
+        for format in StorageFormat::ALL {
+            let encoded = data.encode(format).unwrap();
+            let decoded = AppData::decode(&encoded, format).unwrap();
+            assert_eq!(serde_json::to_string(&decoded).unwrap(), expected, "{:?}", format);
+        }
+        assert_eq!(StorageFormat::from_path("/home/me/.keep_tasks.yml"), StorageFormat::Yaml);
+        assert_eq!(StorageFormat::from_path("tasks"), StorageFormat::Json);
+    }
+
+    #[test]
+    fn trash_expires_after_its_days() {
+        let now = date(2025, 3, 14).and_hms_opt(12, 0, 0).unwrap();
+        let mut data = AppData::new();
+        data.trash = [20, 3, 0]
+            .iter()
+            .map(|&days_ago| TrashedTask {
+                task: task(&format!("{} days ago", days_ago), None),
+                deleted_at: now - chrono::Duration::days(days_ago),
+            })
+            .collect();
+
+        assert_eq!(data.expire_trash(now, 7), 1);
+        let left: Vec<&str> = data.trash.iter().map(|t| t.task.content.as_str()).collect();
+        assert_eq!(left, ["3 days ago", "0 days ago"]);
+        assert_eq!(data.expire_trash(now, 0), 2);
+    }
+
+    #[test]
+    fn filter_matches_ignoring_case() {
+        assert_eq!(find_ignore_case("Buy MILK today", "milk"), Some(4..8));
+        assert_eq!(find_ignore_case("Café au lait", "LAIT"), Some(9..13));
+        assert_eq!(find_ignore_case("Standup", "lunch"), None);
+
+        let spans = highlight_match("Buy MILK today", Some("milk"));
+        let parts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(parts, ["Buy ", "MILK", " today"]);
+        assert_eq!(highlight_match("Standup", Some("")).len(), 1);
+    }
+
+    #[test]
+    fn location_map_links() {
+        assert_eq!(map_url("geo:52.52,13.40"), "geo:52.52,13.40");
+        assert_eq!(map_url(" https://maps.app.goo.gl/abc "), "https://maps.app.goo.gl/abc");
+        assert_eq!(
+            map_url("Café Blue, Main St"),
+            "https://www.openstreetmap.org/search?query=Caf%C3%A9+Blue%2C+Main+St"
+        );
+    }
+
+    #[test]
+    fn focus_stays_with_its_day() {
+        let mut standup = task("Standup", Some(date(2025, 3, 14)));
+        standup.focus = true;
+        standup.move_to(Some(date(2025, 3, 14)));
+        assert!(standup.focus);
+        standup.move_to(Some(date(2025, 3, 15)));
+        assert!(!standup.focus);
+    }
+
+    #[test]
+    fn multi_line_content() {
+        let mut packing = task("Pack\n- passport\n- charger", None);
+        assert_eq!((packing.title(), packing.row_height()), ("Pack", 3));
+        packing.content = String::new();
+        assert_eq!((packing.title(), packing.row_height()), ("", 1));
+    }
+
+    #[test]
+    fn notes_outline_folds_nested_sections() {
+        let notes = "# Work\nmeeting\n## Ideas\nidea\n# Home\n#hashtag";
+        let lines = |folded: &[&str]| -> Vec<(usize, Option<usize>)> {
+            let folded = folded.iter().map(|h| h.to_string()).collect();
+            notes_outline(notes, &folded).iter().map(|row| (row.line, row.folded)).collect()
+        };
+        assert_eq!(heading_level("## Ideas"), Some(2));
+        assert_eq!(heading_level("#hashtag"), None);
+        assert_eq!(lines(&[]).len(), 6);
+        assert_eq!(lines(&["## Ideas"]), [(0, None), (1, None), (2, Some(1)), (4, None), (5, None)]);
+        assert_eq!(lines(&["# Work", "## Ideas"]), [(0, Some(3)), (4, None), (5, None)]);
+        assert_eq!(lines(&["# Home"]).last(), Some(&(4, Some(1))));
+    }
+
+    #[test]
+    fn accents_fall_back_to_defaults() {
+        let mut config = Config::default();
+        assert_eq!(config.accent(ViewMode::Notes), Color::Rgb(150, 100, 200));
+        config.accents.week = "magenta".to_string();
+        config.accents.all = "not a color".to_string();
+        assert_eq!(config.accent(ViewMode::Week), Color::Magenta);
+        assert_eq!(config.accent(ViewMode::All), Color::Yellow);
+    }
+
+    #[test]
+    fn day_boundary_shifts_today() {
+        let at = |h| date(2025, 3, 14).and_hms_opt(h, 30, 0).unwrap();
+        assert_eq!(effective_date(at(1), 0), date(2025, 3, 14));
+        assert_eq!(effective_date(at(1), 3), date(2025, 3, 13));
+        assert_eq!(effective_date(at(3), 3), date(2025, 3, 14));
+    }
+
+    #[test]
+    fn stats_count_each_day() {
+        let today = date(2025, 3, 14);
+        let yesterday = Some(date(2025, 3, 13));
+        let mut finished_late = task("Report", yesterday);
+        finished_late.set_status(Status::Done);
+        finished_late.completed_at = today.and_hms_opt(10, 0, 0);
+        let mut data = AppData::new();
+        data.tasks = vec![finished_late, task("Gym", yesterday), task("Standup", Some(today)), task("Old", Some(date(2025, 3, 1)))];
+
+        let report = data.stats(today, 2);
+        assert_eq!((report.from, report.to), (date(2025, 3, 13), today));
+        let days: Vec<(usize, usize, usize)> = report.days.iter().map(|d| (d.total, d.done, d.completed)).collect();
+        assert_eq!(days, [(2, 1, 0), (1, 0, 1)]);
+        assert_eq!((report.total, report.done, report.completed), (3, 1, 1));
+        assert_eq!(report.completion_rate, Some(1.0 / 3.0));
+        assert_eq!(AppData::new().stats(today, 1).completion_rate, None);
+    }
+
+    #[test]
+    fn ids_stay_unique() {
+        let mut data = AppData::new();
+        data.tasks = vec![task("Legacy", None), task("Five", None), task("Copied five", None), task("Legacy too", None)];
+        data.tasks[1].id = 5;
+        data.tasks[2].id = 5;
+        data.assign_ids();
+        let ids: Vec<u64> = data.tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, [6, 5, 7, 8]);
+
+        // Merged tasks are renumbered, keeping what they were blocked by
+        let mut other = AppData::new();
+        other.tasks = vec![task("Five", None), task("Call back", None), task("Follow up", None)];
+        other.assign_ids();
+        other.tasks[1].blocked_by = Some(other.tasks[0].id);
+        other.tasks[2].blocked_by = Some(other.tasks[1].id);
+        assert_eq!(data.merge(other), (2, 1));
+        let merged: Vec<(u64, Option<u64>)> = data.tasks[4..].iter().map(|t| (t.id, t.blocked_by)).collect();
+        assert_eq!(merged, [(9, Some(5)), (10, Some(9))]);
+    }
+
+    #[test]
+    fn blocked_until_the_blocker_is_done() {
+        let mut data = AppData::new();
+        data.tasks = vec![task("Write report", None), task("Send report", None)];
+        data.assign_ids();
+        assert_eq!((data.tasks[0].id, data.tasks[1].id), (1, 2));
+        data.tasks[1].blocked_by = Some(1);
+
+        assert_eq!(data.blocker(&data.tasks[1]).map(|t| t.id), Some(1));
+        data.tasks[0].set_status(Status::Done);
+        assert!(data.blocker(&data.tasks[1]).is_none());
+    }
+
+    #[test]
+    fn deleted_ids_are_not_reused() {
+        let mut data = AppData::new();
+        data.tasks = vec![task("Send report", None), task("Write report", None)];
+        data.assign_ids();
+        data.tasks[0].blocked_by = Some(2);
+        data.tasks.pop();
+
+        // Saved and loaded again, a new task still doesn't take the deleted one's id
+        let mut data = AppData::decode(&data.encode(StorageFormat::Json).unwrap(), StorageFormat::Json).unwrap();
+        let mut gym = task("Gym", None);
+        gym.id = data.next_id();
+        data.tasks.push(gym);
+        assert_eq!(data.tasks[1].id, 3);
+        assert!(data.blocker(&data.tasks[0]).is_none());
+    }
+
+    #[test]
+    fn logged_time_input_overflow() {
+        assert_eq!(parse_logged_time("+100000000h"), None);
+        assert_eq!(parse_duration_minutes(&format!("{}m", u32::MAX)), Some(u32::MAX));
+        assert_eq!(parse_duration_minutes(&format!("1h{}m", u32::MAX)), None);
+    }
+
+    #[test]
+    fn logged_time_input() {
+        assert_eq!(parse_logged_time("25"), Some(25));
+        assert_eq!(parse_logged_time("+1h30m"), Some(90));
+        assert_eq!(parse_logged_time("-10"), Some(-10));
+        assert_eq!(parse_logged_time("+0"), None);
+        assert_eq!(parse_logged_time("lots"), None);
+    }
+
+    #[test]
+    fn end_time_accepts_duration_after_start() {
+        let format = TimeFormat::TwentyFourHour;
+        let nine = NaiveTime::from_hms_opt(9, 0, 0);
+        assert_eq!(parse_end_time_input("+30", nine, format), Ok(NaiveTime::from_hms_opt(9, 30, 0)));
+        assert_eq!(parse_end_time_input("+1h", nine, format), Ok(NaiveTime::from_hms_opt(10, 0, 0)));
+        assert_eq!(parse_end_time_input("+90m", nine, format), Ok(NaiveTime::from_hms_opt(10, 30, 0)));
+        assert_eq!(parse_end_time_input("10:15", nine, format), Ok(NaiveTime::from_hms_opt(10, 15, 0)));
+        assert!(parse_end_time_input("+30", None, format).is_err());
+        assert!(parse_end_time_input("+16h", nine, format).is_err());
+        assert!(parse_end_time_input("+soon", nine, format).is_err());
+    }
+
+    #[test]
+    fn truncate_counts_characters() {
+        assert_eq!(truncate("Standup", 10), "Standup");
+        assert_eq!(truncate("Write the quarterly report", 10), "Write t...");
+        assert_eq!(truncate("Café au lait", 8), "Café ...");
+    }
+
+    #[test]
+    fn iso_week_input() {
+        let current = date(2025, 6, 4);
+        assert_eq!(parse_iso_week("23", current), Some(date(2025, 6, 2)));
+        assert_eq!(parse_iso_week("W1", current), Some(date(2024, 12, 30)));
+        assert_eq!(parse_iso_week("2026-W05", current), Some(date(2026, 1, 26)));
+        // Dec 30 2025 is already in week 1 of 2026
+        assert_eq!(parse_iso_week("2", date(2025, 12, 30)), Some(date(2026, 1, 5)));
+        assert_eq!(parse_iso_week("2020-53", current), Some(date(2020, 12, 28)));
+        assert_eq!(parse_iso_week("2021-53", current), None);
+        assert_eq!(parse_iso_week("soon", current), None);
+    }
+
+    #[test]
+    fn base64_matches_reference_vectors() {
+        for (input, expected) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9vYmFy")] {
+            assert_eq!(base64_encode(input.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn date_input_empty_means_undated() {
+        let today = date(2025, 3, 14);
+        assert_eq!(parse_date_input("", today), Ok(None));
+        assert_eq!(parse_date_input("fri", today), Ok(Some(date(2025, 3, 21))));
+        assert!(parse_date_input("soon", today).is_err());
     }
-    Ok(())
 }